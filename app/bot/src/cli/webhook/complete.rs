@@ -8,6 +8,12 @@ pub enum Webhook {
     Create(WebhookCreate),
     List(WebhookList),
     Delete(WebhookDelete),
+    Secret(WebhookSecret),
+    Template(WebhookTemplate),
+    Channel(WebhookChannel),
+    Rule(WebhookRule),
+    Sink(WebhookSink),
+    Deliveries(WebhookDeliveries),
 }
 
 #[must_use]
@@ -37,3 +43,139 @@ pub struct WebhookDelete {
     pub talking_channel_id: ChannelId,
     pub webhook_id: WebhookId,
 }
+
+#[must_use]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WebhookSecret {
+    pub user: User,
+    pub in_dm: bool,
+    pub talking_channel_id: ChannelId,
+    pub webhook_id: WebhookId,
+    pub secret: Option<domain::WebhookSecret>,
+}
+
+#[must_use]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WebhookTemplate {
+    pub user: User,
+    pub talking_channel_id: ChannelId,
+    pub webhook_id: WebhookId,
+    pub template: Option<domain::WebhookTemplate>,
+}
+
+#[must_use]
+#[derive(Debug, Clone)]
+pub enum WebhookChannel {
+    Add(WebhookChannelAdd),
+    Remove(WebhookChannelRemove),
+}
+
+#[must_use]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WebhookChannelAdd {
+    pub user: User,
+    pub talking_channel_id: ChannelId,
+    pub webhook_id: WebhookId,
+    pub channel_name: String,
+    /// `channel_name`がチャンネルとして埋め込まれていなければ`None`
+    pub channel_id: Option<ChannelId>,
+    pub filter: Option<domain::EventFilter>,
+}
+
+#[must_use]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WebhookChannelRemove {
+    pub user: User,
+    pub talking_channel_id: ChannelId,
+    pub webhook_id: WebhookId,
+    pub channel_name: String,
+    /// `channel_name`がチャンネルとして埋め込まれていなければ`None`
+    pub channel_id: Option<ChannelId>,
+}
+
+#[must_use]
+#[derive(Debug, Clone)]
+pub enum WebhookRule {
+    Add(WebhookRuleAdd),
+    Remove(WebhookRuleRemove),
+    List(WebhookRuleList),
+}
+
+#[must_use]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WebhookRuleAdd {
+    pub user: User,
+    pub talking_channel_id: ChannelId,
+    pub webhook_id: WebhookId,
+    pub field: domain::RuleField,
+    pub pattern: domain::RulePattern,
+    pub channel_name: String,
+    /// `channel_name`がチャンネルとして埋め込まれていなければ`None`
+    pub channel_id: Option<ChannelId>,
+}
+
+#[must_use]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WebhookRuleRemove {
+    pub user: User,
+    pub talking_channel_id: ChannelId,
+    pub webhook_id: WebhookId,
+    /// 1始まりのルールの番号
+    pub number: usize,
+}
+
+#[must_use]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WebhookRuleList {
+    pub user: User,
+    pub talking_channel_id: ChannelId,
+    pub webhook_id: WebhookId,
+}
+
+#[must_use]
+#[derive(Debug, Clone)]
+pub enum WebhookSink {
+    Add(WebhookSinkAdd),
+    Remove(WebhookSinkRemove),
+    List(WebhookSinkList),
+}
+
+#[must_use]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WebhookSinkAdd {
+    pub user: User,
+    pub in_dm: bool,
+    pub talking_channel_id: ChannelId,
+    pub webhook_id: WebhookId,
+    pub kind: domain::SinkKind,
+    pub url: domain::SinkUrl,
+}
+
+#[must_use]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WebhookSinkRemove {
+    pub user: User,
+    pub talking_channel_id: ChannelId,
+    pub webhook_id: WebhookId,
+    /// 1始まりの転送先の番号
+    pub number: usize,
+}
+
+#[must_use]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WebhookSinkList {
+    pub user: User,
+    pub talking_channel_id: ChannelId,
+    pub webhook_id: WebhookId,
+}
+
+#[must_use]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WebhookDeliveries {
+    pub user: User,
+    pub talking_channel_id: ChannelId,
+    pub webhook_id: WebhookId,
+    pub limit: usize,
+    /// 1始まりの記録の番号。指定されていればペイロードを送信する
+    pub payload: Option<usize>,
+}
@@ -1,4 +1,4 @@
-use clap::{Args, Subcommand};
+use clap::{Args, Subcommand, ValueEnum};
 use domain::OwnerKind;
 use serde::{Deserialize, Serialize};
 use traq_bot_http::payloads::{DirectMessageCreatedPayload, MessageCreatedPayload, types::Message};
@@ -15,6 +15,19 @@ pub enum Webhook {
     Create(WebhookCreate),
     List(WebhookList),
     Delete(WebhookDelete),
+    Secret(WebhookSecret),
+    Template(WebhookTemplate),
+    #[command(subcommand, about = "webhookの追加の投稿先を扱うコマンド")]
+    Channel(WebhookChannel),
+    #[command(subcommand, about = "webhookの投稿先の振り分けルールを扱うコマンド")]
+    Rule(WebhookRule),
+    #[command(
+        subcommand,
+        about = "webhookのイベントの転送先 (Slack, Discord, HTTP) を扱うコマンド"
+    )]
+    Sink(WebhookSink),
+    #[command(about = "webhookが受け取ったリクエストの記録を新しい順に表示する")]
+    Deliveries(WebhookDeliveries),
 }
 
 impl<'a> Incomplete<&'a MessageCreatedPayload> for Webhook {
@@ -25,6 +38,14 @@ impl<'a> Incomplete<&'a MessageCreatedPayload> for Webhook {
             Self::Create(create) => complete::Webhook::Create(create.complete(context)),
             Self::List(list) => complete::Webhook::List(list.complete(context)),
             Self::Delete(delete) => complete::Webhook::Delete(delete.complete(context)),
+            Self::Secret(secret) => complete::Webhook::Secret(secret.complete(context)),
+            Self::Template(template) => complete::Webhook::Template(template.complete(context)),
+            Self::Channel(channel) => complete::Webhook::Channel(channel.complete(context)),
+            Self::Rule(rule) => complete::Webhook::Rule(rule.complete(context)),
+            Self::Sink(sink) => complete::Webhook::Sink(sink.complete(context)),
+            Self::Deliveries(deliveries) => {
+                complete::Webhook::Deliveries(deliveries.complete(context))
+            }
         }
     }
 }
@@ -37,6 +58,14 @@ impl<'a> Incomplete<&'a DirectMessageCreatedPayload> for Webhook {
             Self::Create(create) => complete::Webhook::Create(create.complete(context)),
             Self::List(list) => complete::Webhook::List(list.complete(context)),
             Self::Delete(delete) => complete::Webhook::Delete(delete.complete(context)),
+            Self::Secret(secret) => complete::Webhook::Secret(secret.complete(context)),
+            Self::Template(template) => complete::Webhook::Template(template.complete(context)),
+            Self::Channel(channel) => complete::Webhook::Channel(channel.complete(context)),
+            Self::Rule(rule) => complete::Webhook::Rule(rule.complete(context)),
+            Self::Sink(sink) => complete::Webhook::Sink(sink.complete(context)),
+            Self::Deliveries(deliveries) => {
+                complete::Webhook::Deliveries(deliveries.complete(context))
+            }
         }
     }
 }
@@ -47,7 +76,7 @@ pub struct WebhookCreate {
     #[arg(
         short,
         long,
-        help = "webhook送信先のチャンネル。デフォルトはこのチャンネル"
+        help = "webhook送信先のチャンネル。デフォルトはこのチャンネル。BOTとのDMで省略するとDMに送信します"
     )]
     pub channel: Option<String>,
     #[arg(
@@ -146,3 +175,468 @@ impl<'a> Incomplete<&'a Message> for WebhookDelete {
         }
     }
 }
+
+#[must_use]
+#[derive(Debug, Clone, Args, Deserialize, Serialize)]
+pub struct WebhookSecret {
+    #[arg(help = "シークレットを設定するWebhookのID")]
+    pub id: Uuid,
+    #[arg(help = "署名検証などに使うシークレット。省略するとシークレットを削除します")]
+    pub secret: Option<String>,
+}
+
+impl<'a> Incomplete<(bool, &'a Message)> for WebhookSecret {
+    type Completed = complete::WebhookSecret;
+
+    fn complete(&self, context: (bool, &'a Message)) -> Self::Completed {
+        let (in_dm, context) = context;
+        let user = User {
+            id: context.user.id.into(),
+            name: context.user.name.clone().into(),
+        };
+        complete::WebhookSecret {
+            user,
+            in_dm,
+            talking_channel_id: context.channel_id.into(),
+            webhook_id: self.id.into(),
+            secret: self.secret.clone().map(Into::into),
+        }
+    }
+}
+
+impl<'a> Incomplete<&'a MessageCreatedPayload> for WebhookSecret {
+    type Completed = complete::WebhookSecret;
+
+    fn complete(&self, context: &'a MessageCreatedPayload) -> Self::Completed {
+        self.complete((false, &context.message))
+    }
+}
+
+impl<'a> Incomplete<&'a DirectMessageCreatedPayload> for WebhookSecret {
+    type Completed = complete::WebhookSecret;
+
+    fn complete(&self, context: &'a DirectMessageCreatedPayload) -> Self::Completed {
+        self.complete((true, &context.message))
+    }
+}
+
+#[must_use]
+#[derive(Debug, Clone, Args, Deserialize, Serialize)]
+pub struct WebhookTemplate {
+    #[arg(help = "テンプレートを設定するWebhookのID")]
+    pub id: Uuid,
+    #[arg(
+        help = "`/wh/{id}/generic`で使うテンプレート。`{{ $.path.to.field }}`で値を埋め込めます。省略するとテンプレートを削除します"
+    )]
+    pub template: Option<String>,
+}
+
+impl<'a> Incomplete<&'a Message> for WebhookTemplate {
+    type Completed = complete::WebhookTemplate;
+
+    fn complete(&self, context: &'a Message) -> Self::Completed {
+        let user = User {
+            id: context.user.id.into(),
+            name: context.user.name.clone().into(),
+        };
+        complete::WebhookTemplate {
+            user,
+            talking_channel_id: context.channel_id.into(),
+            webhook_id: self.id.into(),
+            template: self.template.clone().map(Into::into),
+        }
+    }
+}
+
+#[must_use]
+#[derive(Debug, Clone, Subcommand)]
+pub enum WebhookChannel {
+    #[command(about = "投稿先を追加する。同じチャンネルが既にあればフィルタを上書きする")]
+    Add(WebhookChannelAdd),
+    #[command(about = "追加した投稿先を削除する")]
+    Remove(WebhookChannelRemove),
+}
+
+impl<'a> Incomplete<&'a Message> for WebhookChannel {
+    type Completed = complete::WebhookChannel;
+
+    fn complete(&self, context: &'a Message) -> Self::Completed {
+        match self {
+            Self::Add(add) => complete::WebhookChannel::Add(add.complete(context)),
+            Self::Remove(remove) => complete::WebhookChannel::Remove(remove.complete(context)),
+        }
+    }
+}
+
+#[must_use]
+#[derive(Debug, Clone, Args, Deserialize, Serialize)]
+pub struct WebhookChannelAdd {
+    #[arg(help = "投稿先を追加するWebhookのID")]
+    pub id: Uuid,
+    #[arg(help = "追加する投稿先のチャンネル")]
+    pub channel: String,
+    #[arg(
+        short,
+        long,
+        help = "投稿するイベントの種類。カンマ区切りで複数指定でき、`*`は任意の文字列にマッチする。省略すると全てのイベントを投稿します"
+    )]
+    pub filter: Option<String>,
+}
+
+impl<'a> Incomplete<&'a Message> for WebhookChannelAdd {
+    type Completed = complete::WebhookChannelAdd;
+
+    fn complete(&self, context: &'a Message) -> Self::Completed {
+        let user = User {
+            id: context.user.id.into(),
+            name: context.user.name.clone().into(),
+        };
+        complete::WebhookChannelAdd {
+            user,
+            talking_channel_id: context.channel_id.into(),
+            webhook_id: self.id.into(),
+            channel_name: self.channel.clone(),
+            channel_id: find_channel(context, &self.channel),
+            filter: self.filter.clone().map(Into::into),
+        }
+    }
+}
+
+#[must_use]
+#[derive(Debug, Clone, Args, Deserialize, Serialize)]
+pub struct WebhookChannelRemove {
+    #[arg(help = "投稿先を削除するWebhookのID")]
+    pub id: Uuid,
+    #[arg(help = "削除する投稿先のチャンネル")]
+    pub channel: String,
+}
+
+impl<'a> Incomplete<&'a Message> for WebhookChannelRemove {
+    type Completed = complete::WebhookChannelRemove;
+
+    fn complete(&self, context: &'a Message) -> Self::Completed {
+        let user = User {
+            id: context.user.id.into(),
+            name: context.user.name.clone().into(),
+        };
+        complete::WebhookChannelRemove {
+            user,
+            talking_channel_id: context.channel_id.into(),
+            webhook_id: self.id.into(),
+            channel_name: self.channel.clone(),
+            channel_id: find_channel(context, &self.channel),
+        }
+    }
+}
+
+#[must_use]
+#[derive(Debug, Clone, Subcommand)]
+pub enum WebhookRule {
+    #[command(about = "振り分けルールを末尾に追加する")]
+    Add(WebhookRuleAdd),
+    #[command(about = "振り分けルールを削除する")]
+    Remove(WebhookRuleRemove),
+    #[command(about = "振り分けルールの一覧を表示する")]
+    List(WebhookRuleList),
+}
+
+impl<'a> Incomplete<&'a Message> for WebhookRule {
+    type Completed = complete::WebhookRule;
+
+    fn complete(&self, context: &'a Message) -> Self::Completed {
+        match self {
+            Self::Add(add) => complete::WebhookRule::Add(add.complete(context)),
+            Self::Remove(remove) => complete::WebhookRule::Remove(remove.complete(context)),
+            Self::List(list) => complete::WebhookRule::List(list.complete(context)),
+        }
+    }
+}
+
+#[must_use]
+#[derive(Debug, Clone, Copy, ValueEnum, Deserialize, Serialize)]
+pub enum RuleField {
+    #[value(help = "`owner/name`形式のリポジトリ名")]
+    Repo,
+    #[value(help = "Issue・Pull Requestなどのラベル")]
+    Label,
+    #[value(help = "GitHub Actionsのワークフローなどの結果 (success, failure, ...)")]
+    Conclusion,
+    #[value(help = "イベントの種類 (push, issues, ...)")]
+    Event,
+}
+
+impl From<RuleField> for domain::RuleField {
+    fn from(value: RuleField) -> Self {
+        match value {
+            RuleField::Repo => Self::Repo,
+            RuleField::Label => Self::Label,
+            RuleField::Conclusion => Self::Conclusion,
+            RuleField::Event => Self::Event,
+        }
+    }
+}
+
+#[must_use]
+#[derive(Debug, Clone, Args, Deserialize, Serialize)]
+pub struct WebhookRuleAdd {
+    #[arg(help = "ルールを追加するWebhookのID")]
+    pub id: Uuid,
+    #[arg(value_enum, help = "比較するペイロードの値")]
+    pub field: RuleField,
+    #[arg(help = "値のパターン。`*`は任意の文字列にマッチする")]
+    pub pattern: String,
+    #[arg(help = "一致したときの投稿先のチャンネル")]
+    pub channel: String,
+}
+
+impl<'a> Incomplete<&'a Message> for WebhookRuleAdd {
+    type Completed = complete::WebhookRuleAdd;
+
+    fn complete(&self, context: &'a Message) -> Self::Completed {
+        let user = User {
+            id: context.user.id.into(),
+            name: context.user.name.clone().into(),
+        };
+        complete::WebhookRuleAdd {
+            user,
+            talking_channel_id: context.channel_id.into(),
+            webhook_id: self.id.into(),
+            field: self.field.into(),
+            pattern: self.pattern.clone().into(),
+            channel_name: self.channel.clone(),
+            channel_id: find_channel(context, &self.channel),
+        }
+    }
+}
+
+#[must_use]
+#[derive(Debug, Clone, Args, Deserialize, Serialize)]
+pub struct WebhookRuleRemove {
+    #[arg(help = "ルールを削除するWebhookのID")]
+    pub id: Uuid,
+    #[arg(help = "削除するルールの番号。`webhook rule list`で確認できます")]
+    pub number: usize,
+}
+
+impl<'a> Incomplete<&'a Message> for WebhookRuleRemove {
+    type Completed = complete::WebhookRuleRemove;
+
+    fn complete(&self, context: &'a Message) -> Self::Completed {
+        let user = User {
+            id: context.user.id.into(),
+            name: context.user.name.clone().into(),
+        };
+        complete::WebhookRuleRemove {
+            user,
+            talking_channel_id: context.channel_id.into(),
+            webhook_id: self.id.into(),
+            number: self.number,
+        }
+    }
+}
+
+#[must_use]
+#[derive(Debug, Clone, Args, Deserialize, Serialize)]
+pub struct WebhookRuleList {
+    #[arg(help = "ルールを表示するWebhookのID")]
+    pub id: Uuid,
+}
+
+impl<'a> Incomplete<&'a Message> for WebhookRuleList {
+    type Completed = complete::WebhookRuleList;
+
+    fn complete(&self, context: &'a Message) -> Self::Completed {
+        let user = User {
+            id: context.user.id.into(),
+            name: context.user.name.clone().into(),
+        };
+        complete::WebhookRuleList {
+            user,
+            talking_channel_id: context.channel_id.into(),
+            webhook_id: self.id.into(),
+        }
+    }
+}
+
+#[must_use]
+#[derive(Debug, Clone, Subcommand)]
+pub enum WebhookSink {
+    #[command(about = "転送先を末尾に追加する。URLを含むためBOTとのDMで実行してください")]
+    Add(WebhookSinkAdd),
+    #[command(about = "転送先を削除する")]
+    Remove(WebhookSinkRemove),
+    #[command(about = "転送先の一覧をDMで表示する")]
+    List(WebhookSinkList),
+}
+
+impl<'a> Incomplete<(bool, &'a Message)> for WebhookSink {
+    type Completed = complete::WebhookSink;
+
+    fn complete(&self, context: (bool, &'a Message)) -> Self::Completed {
+        let (_, message) = context;
+        match self {
+            Self::Add(add) => complete::WebhookSink::Add(add.complete(context)),
+            Self::Remove(remove) => complete::WebhookSink::Remove(remove.complete(message)),
+            Self::List(list) => complete::WebhookSink::List(list.complete(message)),
+        }
+    }
+}
+
+impl<'a> Incomplete<&'a MessageCreatedPayload> for WebhookSink {
+    type Completed = complete::WebhookSink;
+
+    fn complete(&self, context: &'a MessageCreatedPayload) -> Self::Completed {
+        self.complete((false, &context.message))
+    }
+}
+
+impl<'a> Incomplete<&'a DirectMessageCreatedPayload> for WebhookSink {
+    type Completed = complete::WebhookSink;
+
+    fn complete(&self, context: &'a DirectMessageCreatedPayload) -> Self::Completed {
+        self.complete((true, &context.message))
+    }
+}
+
+#[must_use]
+#[derive(Debug, Clone, Copy, ValueEnum, Deserialize, Serialize)]
+pub enum SinkKind {
+    #[value(help = "SlackのIncoming Webhook")]
+    Slack,
+    #[value(help = "DiscordのWebhook")]
+    Discord,
+    #[value(help = "`kind`と`body`を含むJSONをPOSTする任意のエンドポイント")]
+    Http,
+}
+
+impl From<SinkKind> for domain::SinkKind {
+    fn from(value: SinkKind) -> Self {
+        match value {
+            SinkKind::Slack => Self::Slack,
+            SinkKind::Discord => Self::Discord,
+            SinkKind::Http => Self::Http,
+        }
+    }
+}
+
+#[must_use]
+#[derive(Debug, Clone, Args, Deserialize, Serialize)]
+pub struct WebhookSinkAdd {
+    #[arg(help = "転送先を追加するWebhookのID")]
+    pub id: Uuid,
+    #[arg(value_enum, help = "転送先の種類")]
+    pub kind: SinkKind,
+    #[arg(help = "転送先のURL。`http://`または`https://`で始まる必要があります")]
+    pub url: String,
+}
+
+impl<'a> Incomplete<(bool, &'a Message)> for WebhookSinkAdd {
+    type Completed = complete::WebhookSinkAdd;
+
+    fn complete(&self, context: (bool, &'a Message)) -> Self::Completed {
+        let (in_dm, context) = context;
+        let user = User {
+            id: context.user.id.into(),
+            name: context.user.name.clone().into(),
+        };
+        complete::WebhookSinkAdd {
+            user,
+            in_dm,
+            talking_channel_id: context.channel_id.into(),
+            webhook_id: self.id.into(),
+            kind: self.kind.into(),
+            url: self.url.clone().into(),
+        }
+    }
+}
+
+#[must_use]
+#[derive(Debug, Clone, Args, Deserialize, Serialize)]
+pub struct WebhookSinkRemove {
+    #[arg(help = "転送先を削除するWebhookのID")]
+    pub id: Uuid,
+    #[arg(help = "削除する転送先の番号。`webhook sink list`で確認できます")]
+    pub number: usize,
+}
+
+impl<'a> Incomplete<&'a Message> for WebhookSinkRemove {
+    type Completed = complete::WebhookSinkRemove;
+
+    fn complete(&self, context: &'a Message) -> Self::Completed {
+        let user = User {
+            id: context.user.id.into(),
+            name: context.user.name.clone().into(),
+        };
+        complete::WebhookSinkRemove {
+            user,
+            talking_channel_id: context.channel_id.into(),
+            webhook_id: self.id.into(),
+            number: self.number,
+        }
+    }
+}
+
+#[must_use]
+#[derive(Debug, Clone, Args, Deserialize, Serialize)]
+pub struct WebhookSinkList {
+    #[arg(help = "転送先を表示するWebhookのID")]
+    pub id: Uuid,
+}
+
+impl<'a> Incomplete<&'a Message> for WebhookSinkList {
+    type Completed = complete::WebhookSinkList;
+
+    fn complete(&self, context: &'a Message) -> Self::Completed {
+        let user = User {
+            id: context.user.id.into(),
+            name: context.user.name.clone().into(),
+        };
+        complete::WebhookSinkList {
+            user,
+            talking_channel_id: context.channel_id.into(),
+            webhook_id: self.id.into(),
+        }
+    }
+}
+
+#[must_use]
+#[derive(Debug, Clone, Args, Deserialize, Serialize)]
+pub struct WebhookDeliveries {
+    #[arg(help = "記録を表示するWebhookのID")]
+    pub id: Uuid,
+    #[arg(short = 'n', long, default_value_t = 10, help = "表示する件数")]
+    pub limit: usize,
+    #[arg(
+        short,
+        long,
+        help = "指定した番号の記録のペイロードをDMで送信する。番号は一覧で確認できます"
+    )]
+    pub payload: Option<usize>,
+}
+
+impl<'a> Incomplete<&'a Message> for WebhookDeliveries {
+    type Completed = complete::WebhookDeliveries;
+
+    fn complete(&self, context: &'a Message) -> Self::Completed {
+        let user = User {
+            id: context.user.id.into(),
+            name: context.user.name.clone().into(),
+        };
+        complete::WebhookDeliveries {
+            user,
+            talking_channel_id: context.channel_id.into(),
+            webhook_id: self.id.into(),
+            limit: self.limit,
+            payload: self.payload,
+        }
+    }
+}
+
+/// メッセージに埋め込まれたチャンネルのID
+fn find_channel(context: &Message, channel: &str) -> Option<domain::ChannelId> {
+    context
+        .embedded
+        .iter()
+        .find(|e| e.raw == channel && e.r#type == "channel")
+        .map(|e| e.id.into())
+}
@@ -1,13 +1,29 @@
+use std::time::SystemTime;
+
 use futures::TryFutureExt;
 use indoc::formatdoc;
 use uuid::Uuid;
 
-use domain::{Failure, Infra, Owner, OwnerKind, Repository, TraqClient, User};
+use domain::{
+    ChannelId, Destination, Failure, Infra, Owner, OwnerKind, Repository, TraqClient, User,
+    WebhookId,
+};
 
 use super::BotImplInner;
-use crate::cli::webhook::complete::{Webhook, WebhookCreate, WebhookDelete, WebhookList};
+use crate::cli::webhook::complete::{
+    Webhook, WebhookChannel, WebhookChannelAdd, WebhookChannelRemove, WebhookCreate, WebhookDelete,
+    WebhookDeliveries, WebhookList, WebhookRule, WebhookRuleAdd, WebhookRuleList,
+    WebhookRuleRemove, WebhookSecret, WebhookSink, WebhookSinkAdd, WebhookSinkList,
+    WebhookSinkRemove, WebhookTemplate,
+};
 use crate::error::Error;
 
+/// `webhook deliveries`で一度に表示できる件数
+const DELIVERIES_MAX_LIMIT: usize = 50;
+
+/// DMで送るペイロードの最大文字数
+const PAYLOAD_MAX_CHARS: usize = 9000;
+
 impl BotImplInner {
     pub(super) async fn handle_webhook_command<I>(
         &self,
@@ -17,11 +33,28 @@ impl BotImplInner {
     where
         I: Infra,
     {
-        use Webhook::{Create, Delete, List};
+        use Webhook::{Channel, Create, Delete, Deliveries, List, Rule, Secret, Sink, Template};
         match wh {
             Create(create) => self.handle_webhook_create(infra, create).await,
             Delete(delete) => self.handle_webhook_delete(infra, delete).await,
             List(list) => self.handle_webhook_list(infra, list).await,
+            Secret(secret) => self.handle_webhook_secret(infra, secret).await,
+            Template(template) => self.handle_webhook_template(infra, template).await,
+            Channel(WebhookChannel::Add(add)) => self.handle_webhook_channel_add(infra, add).await,
+            Channel(WebhookChannel::Remove(remove)) => {
+                self.handle_webhook_channel_remove(infra, remove).await
+            }
+            Rule(WebhookRule::Add(add)) => self.handle_webhook_rule_add(infra, add).await,
+            Rule(WebhookRule::Remove(remove)) => {
+                self.handle_webhook_rule_remove(infra, remove).await
+            }
+            Rule(WebhookRule::List(list)) => self.handle_webhook_rule_list(infra, list).await,
+            Sink(WebhookSink::Add(add)) => self.handle_webhook_sink_add(infra, add).await,
+            Sink(WebhookSink::Remove(remove)) => {
+                self.handle_webhook_sink_remove(infra, remove).await
+            }
+            Sink(WebhookSink::List(list)) => self.handle_webhook_sink_list(infra, list).await,
+            Deliveries(deliveries) => self.handle_webhook_deliveries(infra, deliveries).await,
         }
     }
 
@@ -32,8 +65,9 @@ impl BotImplInner {
         let client = infra.traq_client();
         let repo = infra.repo();
 
-        if create.channel_dm {
-            let message = "エラー: Webhook投稿先にDMを指定することはできません";
+        // DMに投稿できるのは所有者本人だけ
+        if create.channel_dm && create.owner_kind == OwnerKind::Group {
+            let message = "エラー: DMを投稿先にできるのは所有者が1人のWebhookのみです";
             client
                 .send_message(&create.talking_channel_id, message, false)
                 .await?;
@@ -74,7 +108,7 @@ impl BotImplInner {
         // webhook生成してDBに追加
         let id = Uuid::now_v7().into();
         let channel_id = create.channel_id;
-        let webhook = domain::Webhook::new(id, channel_id, owner);
+        let webhook = domain::Webhook::new(id, channel_id, owner).with_dm(create.channel_dm);
         repo.add_webhook(&webhook).await?;
 
         let message_title = match webhook.owner.kind() {
@@ -84,7 +118,7 @@ impl BotImplInner {
             ),
             OwnerKind::SingleUser => String::from("Webhookが作成されました"),
         };
-        let channel_path = client.get_channel_path(&webhook.channel_id).await?;
+        let channel_path = destination_path(client, &webhook).await?;
         let message = formatdoc! {
             r"
                 ### {message_title}
@@ -96,7 +130,25 @@ impl BotImplInner {
                 - GitHub: https://cnvtr.trap.show/wh/{id}/github
                 - Gitea: https://cnvtr.trap.show/wh/{id}/gitea
                 - ClickUp: https://cnvtr.trap.show/wh/{id}/clickup
+                - Sentry: https://cnvtr.trap.show/wh/{id}/sentry
+                - Alertmanager: https://cnvtr.trap.show/wh/{id}/alertmanager
+                - Grafana: https://cnvtr.trap.show/wh/{id}/grafana
+                - Jira: https://cnvtr.trap.show/wh/{id}/jira
+                - Linear: https://cnvtr.trap.show/wh/{id}/linear
+                - Backlog: https://cnvtr.trap.show/wh/{id}/backlog?space=<スペースのドメイン>
+                - コンテナレジストリ: https://cnvtr.trap.show/wh/{id}/registry
+                - 死活監視: https://cnvtr.trap.show/wh/{id}/uptime
+                - Jenkins: https://cnvtr.trap.show/wh/{id}/jenkins
+                - Vercel: https://cnvtr.trap.show/wh/{id}/vercel
+                - Netlify: https://cnvtr.trap.show/wh/{id}/netlify
+                - Stripe: https://cnvtr.trap.show/wh/{id}/stripe
+                - Slack互換: https://cnvtr.trap.show/wh/{id}/slack
+                - Discord互換: https://cnvtr.trap.show/wh/{id}/discord
+                - 汎用JSON: https://cnvtr.trap.show/wh/{id}/generic
+                - テキスト: https://cnvtr.trap.show/wh/{id}/text
 
+                署名検証用のシークレットを設定する場合はDMで `@{bot_name} webhook secret {id} <secret>` と投稿してください
+                汎用JSONのテンプレートを設定する場合は `@{bot_name} webhook template {id} '<template>'` と投稿してください
                 Webhookを削除する場合は `@{bot_name} webhook delete {id}` と投稿してください
             ",
             bot_name = &self.name,
@@ -116,28 +168,17 @@ impl BotImplInner {
         let repo = infra.repo();
         let client = infra.traq_client();
 
-        let webhook = match repo.find_webhook(&delete.webhook_id).await {
-            Ok(w) => w,
-            Err(Failure::Reject(r)) => {
-                let message = format!("エラー: {r}");
-                client
-                    .send_message(&delete.talking_channel_id, &message, true)
-                    .await?;
-                return Ok(());
-            }
-            Err(Failure::Error(e)) => return Err(e.into()),
-        };
-        let own_users_contain_self = webhook.owner.iter_users().any(|u| u.id == delete.user.id);
-        if !own_users_contain_self {
-            let message = format!(
-                "エラー: webhook所有者に @{name} が含まれていません",
-                name = delete.user.name,
-            );
-            client
-                .send_message(&delete.talking_channel_id, &message, true)
-                .await?;
+        let owned = self
+            .find_owned_webhook(
+                infra,
+                &delete.webhook_id,
+                &delete.user,
+                &delete.talking_channel_id,
+            )
+            .await?;
+        let Some(webhook) = owned else {
             return Ok(());
-        }
+        };
         repo.remove_webhook(&webhook).await?;
         let own_users = webhook.owner.iter_users();
         let message = format!("Webhook {id} を削除しました", id = delete.webhook_id);
@@ -154,18 +195,34 @@ impl BotImplInner {
 
         let webhooks = infra.repo().filter_webhook_by_user(&list.user).await?;
         let channel_paths = webhooks.iter().map(|w| {
-            client
-                .get_channel_path(&w.channel_id)
-                .map_ok(move |c| (w, c))
+            let extra_paths = w.channels.iter().map(|c| {
+                client
+                    .get_channel_path(&c.channel_id)
+                    .map_ok(move |p| match c.filter() {
+                        Some(f) => format!("\n- {p} (`{f}`)"),
+                        None => format!("\n- {p}"),
+                    })
+            });
+            futures::future::try_join(
+                destination_path(client, w),
+                futures::future::try_join_all(extra_paths),
+            )
+            .map_ok(move |(c, extra)| (w, c, extra))
         });
         let channel_paths = futures::future::try_join_all(channel_paths).await?;
         let message = channel_paths
             .into_iter()
-            .map(|(w, c)| {
+            .map(|(w, c, extra)| {
+                // 追加の投稿先がなければ何も表示しない
+                let extra = if extra.is_empty() {
+                    String::new()
+                } else {
+                    format!("\n追加の投稿先:{}", extra.concat())
+                };
                 formatdoc! {
                     r"
                         Webhook ID: {id}
-                        投稿先チャンネル: {c}
+                        投稿先チャンネル: {c}{extra}
                     ",
                     id = w.id
                 }
@@ -177,4 +234,623 @@ impl BotImplInner {
             .await?;
         Ok(())
     }
+
+    async fn handle_webhook_secret<I>(&self, infra: &I, secret: WebhookSecret) -> Result<(), Error>
+    where
+        I: Infra,
+    {
+        let repo = infra.repo();
+        let client = infra.traq_client();
+
+        // チャンネルに投稿されたシークレットは誰でも見られてしまう
+        if !secret.in_dm && secret.secret.is_some() {
+            let message = "エラー: シークレットの設定はBOTとのDMで行ってください";
+            client
+                .send_message(&secret.talking_channel_id, message, false)
+                .await?;
+            return Ok(());
+        }
+        let owned = self
+            .find_owned_webhook(
+                infra,
+                &secret.webhook_id,
+                &secret.user,
+                &secret.talking_channel_id,
+            )
+            .await?;
+        let Some(webhook) = owned else {
+            return Ok(());
+        };
+        let webhook = webhook.with_secret(secret.secret);
+        repo.update_webhook(&webhook).await?;
+        let message = if webhook.secret.is_some() {
+            format!("Webhook {id} のシークレットを設定しました", id = webhook.id)
+        } else {
+            format!("Webhook {id} のシークレットを削除しました", id = webhook.id)
+        };
+        client
+            .send_direct_message(&secret.user.id, &message, false)
+            .await?;
+        Ok(())
+    }
+
+    async fn handle_webhook_template<I>(
+        &self,
+        infra: &I,
+        template: WebhookTemplate,
+    ) -> Result<(), Error>
+    where
+        I: Infra,
+    {
+        let repo = infra.repo();
+        let client = infra.traq_client();
+
+        let owned = self
+            .find_owned_webhook(
+                infra,
+                &template.webhook_id,
+                &template.user,
+                &template.talking_channel_id,
+            )
+            .await?;
+        let Some(webhook) = owned else {
+            return Ok(());
+        };
+        let webhook = webhook.with_template(template.template);
+        repo.update_webhook(&webhook).await?;
+        let message = match webhook.template() {
+            Some(t) => format!(
+                "Webhook {id} のテンプレートを設定しました\n```\n{t}\n```",
+                id = webhook.id
+            ),
+            None => format!("Webhook {id} のテンプレートを削除しました", id = webhook.id),
+        };
+        client
+            .send_message(&template.talking_channel_id, &message, false)
+            .await?;
+        Ok(())
+    }
+
+    async fn handle_webhook_channel_add<I>(
+        &self,
+        infra: &I,
+        add: WebhookChannelAdd,
+    ) -> Result<(), Error>
+    where
+        I: Infra,
+    {
+        let repo = infra.repo();
+        let client = infra.traq_client();
+
+        let Some(channel_id) = add.channel_id else {
+            let message = format!(
+                "エラー: {name} はチャンネルではありません",
+                name = add.channel_name
+            );
+            client
+                .send_message(&add.talking_channel_id, &message, false)
+                .await?;
+            return Ok(());
+        };
+        let owned = self
+            .find_owned_webhook(infra, &add.webhook_id, &add.user, &add.talking_channel_id)
+            .await?;
+        let Some(webhook) = owned else {
+            return Ok(());
+        };
+        if webhook.channel_id == channel_id {
+            let message = "エラー: Webhookの投稿先チャンネルは追加の投稿先にできません";
+            client
+                .send_message(&add.talking_channel_id, message, false)
+                .await?;
+            return Ok(());
+        }
+        let channel = domain::WebhookChannel::new(channel_id, add.filter.clone());
+        let webhook = webhook.with_channel(channel);
+        repo.update_webhook(&webhook).await?;
+        let channel_path = client.get_channel_path(&channel_id).await?;
+        let message = match add.filter {
+            Some(f) => format!(
+                "Webhook {id} の投稿先に {channel_path} を追加しました (フィルタ: `{f}`)",
+                id = webhook.id
+            ),
+            None => format!(
+                "Webhook {id} の投稿先に {channel_path} を追加しました",
+                id = webhook.id
+            ),
+        };
+        client
+            .send_message(&add.talking_channel_id, &message, false)
+            .await?;
+        Ok(())
+    }
+
+    async fn handle_webhook_channel_remove<I>(
+        &self,
+        infra: &I,
+        remove: WebhookChannelRemove,
+    ) -> Result<(), Error>
+    where
+        I: Infra,
+    {
+        let repo = infra.repo();
+        let client = infra.traq_client();
+
+        let owned = self
+            .find_owned_webhook(
+                infra,
+                &remove.webhook_id,
+                &remove.user,
+                &remove.talking_channel_id,
+            )
+            .await?;
+        let Some(webhook) = owned else {
+            return Ok(());
+        };
+        let channel_id = remove
+            .channel_id
+            .filter(|c| webhook.channels.iter().any(|wc| &wc.channel_id == c));
+        let Some(channel_id) = channel_id else {
+            let message = format!(
+                "エラー: {name} はWebhook {id} の追加の投稿先ではありません",
+                name = remove.channel_name,
+                id = webhook.id
+            );
+            client
+                .send_message(&remove.talking_channel_id, &message, false)
+                .await?;
+            return Ok(());
+        };
+        let webhook = webhook.without_channel(&channel_id);
+        repo.update_webhook(&webhook).await?;
+        let message = format!(
+            "Webhook {id} の投稿先から {name} を削除しました",
+            id = webhook.id,
+            name = remove.channel_name
+        );
+        client
+            .send_message(&remove.talking_channel_id, &message, false)
+            .await?;
+        Ok(())
+    }
+
+    async fn handle_webhook_rule_add<I>(&self, infra: &I, add: WebhookRuleAdd) -> Result<(), Error>
+    where
+        I: Infra,
+    {
+        let repo = infra.repo();
+        let client = infra.traq_client();
+
+        let Some(channel_id) = add.channel_id else {
+            let message = format!(
+                "エラー: {name} はチャンネルではありません",
+                name = add.channel_name
+            );
+            client
+                .send_message(&add.talking_channel_id, &message, false)
+                .await?;
+            return Ok(());
+        };
+        let owned = self
+            .find_owned_webhook(infra, &add.webhook_id, &add.user, &add.talking_channel_id)
+            .await?;
+        let Some(webhook) = owned else {
+            return Ok(());
+        };
+        let rule = domain::WebhookRule::new(add.field, add.pattern, channel_id);
+        let webhook = webhook.with_rule(rule);
+        repo.update_webhook(&webhook).await?;
+        let message = format!(
+            "Webhook {id} に振り分けルール {number} を追加しました",
+            id = webhook.id,
+            number = webhook.rules.len()
+        );
+        client
+            .send_message(&add.talking_channel_id, &message, false)
+            .await?;
+        Ok(())
+    }
+
+    async fn handle_webhook_rule_remove<I>(
+        &self,
+        infra: &I,
+        remove: WebhookRuleRemove,
+    ) -> Result<(), Error>
+    where
+        I: Infra,
+    {
+        let repo = infra.repo();
+        let client = infra.traq_client();
+
+        let owned = self
+            .find_owned_webhook(
+                infra,
+                &remove.webhook_id,
+                &remove.user,
+                &remove.talking_channel_id,
+            )
+            .await?;
+        let Some(webhook) = owned else {
+            return Ok(());
+        };
+        if !(1..=webhook.rules.len()).contains(&remove.number) {
+            let message = format!(
+                "エラー: Webhook {id} に振り分けルール {number} はありません",
+                id = webhook.id,
+                number = remove.number
+            );
+            client
+                .send_message(&remove.talking_channel_id, &message, false)
+                .await?;
+            return Ok(());
+        }
+        let webhook = webhook.without_rule(remove.number - 1);
+        repo.update_webhook(&webhook).await?;
+        let message = format!(
+            "Webhook {id} の振り分けルール {number} を削除しました",
+            id = webhook.id,
+            number = remove.number
+        );
+        client
+            .send_message(&remove.talking_channel_id, &message, false)
+            .await?;
+        Ok(())
+    }
+
+    async fn handle_webhook_rule_list<I>(
+        &self,
+        infra: &I,
+        list: WebhookRuleList,
+    ) -> Result<(), Error>
+    where
+        I: Infra,
+    {
+        let client = infra.traq_client();
+
+        let owned = self
+            .find_owned_webhook(
+                infra,
+                &list.webhook_id,
+                &list.user,
+                &list.talking_channel_id,
+            )
+            .await?;
+        let Some(webhook) = owned else {
+            return Ok(());
+        };
+        let rules = webhook.rules.iter().zip(1..).map(|(r, number)| {
+            client.get_channel_path(&r.channel_id).map_ok(move |c| {
+                format!(
+                    "{number}. {field} `{pattern}` → {c}\n",
+                    field = r.field,
+                    pattern = r.pattern
+                )
+            })
+        });
+        let rules = futures::future::try_join_all(rules).await?;
+        let rules = if rules.is_empty() {
+            String::from("ルールはありません\n")
+        } else {
+            rules.concat()
+        };
+        let default_path = destination_path(client, &webhook).await?;
+        let message = formatdoc! {
+            r"
+                ### Webhook {id} の振り分けルール
+
+                {rules}
+                どのルールにも一致しない場合: {default_path}
+            ",
+            id = webhook.id,
+        };
+        client
+            .send_message(&list.talking_channel_id, message.trim(), false)
+            .await?;
+        Ok(())
+    }
+
+    async fn handle_webhook_sink_add<I>(&self, infra: &I, add: WebhookSinkAdd) -> Result<(), Error>
+    where
+        I: Infra,
+    {
+        let repo = infra.repo();
+        let client = infra.traq_client();
+
+        // Incoming WebhookのURLは知っていれば誰でも投稿できてしまう
+        if !add.in_dm {
+            let message = "エラー: 転送先の追加はBOTとのDMで行ってください";
+            client
+                .send_message(&add.talking_channel_id, message, false)
+                .await?;
+            return Ok(());
+        }
+        let url: &str = add.url.as_ref();
+        if !(url.starts_with("https://") || url.starts_with("http://")) {
+            let message = "エラー: 転送先のURLは`http://`または`https://`で始まる必要があります";
+            client
+                .send_message(&add.talking_channel_id, message, false)
+                .await?;
+            return Ok(());
+        }
+        let owned = self
+            .find_owned_webhook(infra, &add.webhook_id, &add.user, &add.talking_channel_id)
+            .await?;
+        let Some(webhook) = owned else {
+            return Ok(());
+        };
+        let sink = domain::Sink {
+            id: Uuid::now_v7().into(),
+            kind: add.kind,
+            url: add.url,
+        };
+        let webhook = webhook.with_sink(sink);
+        repo.update_webhook(&webhook).await?;
+        let message = format!(
+            "Webhook {id} に転送先 {number} ({kind}) を追加しました",
+            id = webhook.id,
+            number = webhook.sinks.len(),
+            kind = add.kind
+        );
+        client
+            .send_direct_message(&add.user.id, &message, false)
+            .await?;
+        Ok(())
+    }
+
+    async fn handle_webhook_sink_remove<I>(
+        &self,
+        infra: &I,
+        remove: WebhookSinkRemove,
+    ) -> Result<(), Error>
+    where
+        I: Infra,
+    {
+        let repo = infra.repo();
+        let client = infra.traq_client();
+
+        let owned = self
+            .find_owned_webhook(
+                infra,
+                &remove.webhook_id,
+                &remove.user,
+                &remove.talking_channel_id,
+            )
+            .await?;
+        let Some(webhook) = owned else {
+            return Ok(());
+        };
+        if !(1..=webhook.sinks.len()).contains(&remove.number) {
+            let message = format!(
+                "エラー: Webhook {id} に転送先 {number} はありません",
+                id = webhook.id,
+                number = remove.number
+            );
+            client
+                .send_message(&remove.talking_channel_id, &message, false)
+                .await?;
+            return Ok(());
+        }
+        let webhook = webhook.without_sink(remove.number - 1);
+        repo.update_webhook(&webhook).await?;
+        let message = format!(
+            "Webhook {id} の転送先 {number} を削除しました",
+            id = webhook.id,
+            number = remove.number
+        );
+        client
+            .send_message(&remove.talking_channel_id, &message, false)
+            .await?;
+        Ok(())
+    }
+
+    async fn handle_webhook_sink_list<I>(
+        &self,
+        infra: &I,
+        list: WebhookSinkList,
+    ) -> Result<(), Error>
+    where
+        I: Infra,
+    {
+        let client = infra.traq_client();
+
+        let owned = self
+            .find_owned_webhook(
+                infra,
+                &list.webhook_id,
+                &list.user,
+                &list.talking_channel_id,
+            )
+            .await?;
+        let Some(webhook) = owned else {
+            return Ok(());
+        };
+        // URLを含むので一覧はDMにのみ送る
+        let sinks = webhook
+            .sinks
+            .iter()
+            .zip(1..)
+            .map(|(s, number)| format!("{number}. {kind} `{url}`\n", kind = s.kind, url = s.url))
+            .collect::<Vec<_>>();
+        let sinks = if sinks.is_empty() {
+            String::from("転送先はありません\n")
+        } else {
+            sinks.concat()
+        };
+        let message = formatdoc! {
+            r"
+                ### Webhook {id} の転送先
+
+                {sinks}
+            ",
+            id = webhook.id,
+        };
+        client
+            .send_direct_message(&list.user.id, message.trim(), false)
+            .await?;
+        Ok(())
+    }
+
+    async fn handle_webhook_deliveries<I>(
+        &self,
+        infra: &I,
+        deliveries: WebhookDeliveries,
+    ) -> Result<(), Error>
+    where
+        I: Infra,
+    {
+        let repo = infra.repo();
+        let client = infra.traq_client();
+
+        let owned = self
+            .find_owned_webhook(
+                infra,
+                &deliveries.webhook_id,
+                &deliveries.user,
+                &deliveries.talking_channel_id,
+            )
+            .await?;
+        let Some(webhook) = owned else {
+            return Ok(());
+        };
+        let limit = deliveries
+            .limit
+            .max(deliveries.payload.unwrap_or_default())
+            .min(DELIVERIES_MAX_LIMIT);
+        let records = repo
+            .filter_deliveries_by_webhook(&webhook.id, limit)
+            .await?;
+        // ペイロードには秘密の情報が含まれることがあるのでDMにのみ送る
+        if let Some(number) = deliveries.payload {
+            let Some(record) = number.checked_sub(1).and_then(|i| records.get(i)) else {
+                let message = format!(
+                    "エラー: Webhook {id} に記録 {number} はありません",
+                    id = webhook.id,
+                );
+                client
+                    .send_message(&deliveries.talking_channel_id, &message, false)
+                    .await?;
+                return Ok(());
+            };
+            let payload = truncate_chars(&record.payload, PAYLOAD_MAX_CHARS);
+            client
+                .send_code_dm(&deliveries.user.id, "json", &payload)
+                .await?;
+            return Ok(());
+        }
+        let now = SystemTime::now();
+        let records = records
+            .iter()
+            .take(deliveries.limit)
+            .zip(1..)
+            .map(|(d, number)| {
+                let elapsed = d
+                    .received_at()
+                    .and_then(|t| now.duration_since(t).ok())
+                    .map_or_else(|| String::from("?"), |e| elapsed_str(e.as_secs()));
+                let event = d
+                    .event
+                    .as_deref()
+                    .map(|e| format!(" `{e}`"))
+                    .unwrap_or_default();
+                let error = d
+                    .error
+                    .as_deref()
+                    .map(|e| format!(": {e}"))
+                    .unwrap_or_default();
+                format!(
+                    "{number}. {elapsed} {provider}{event} **{status}**{error}\n",
+                    provider = d.provider,
+                    status = d.status
+                )
+            })
+            .collect::<Vec<_>>();
+        let records = if records.is_empty() {
+            String::from("記録はありません\n")
+        } else {
+            records.concat()
+        };
+        let message = formatdoc! {
+            r"
+                ### Webhook {id} が受け取ったリクエスト
+
+                {records}
+            ",
+            id = webhook.id,
+        };
+        client
+            .send_message(&deliveries.talking_channel_id, message.trim(), false)
+            .await?;
+        Ok(())
+    }
+
+    /// `webhook_id`のWebhookのうち、`user`が所有者に含まれるもの
+    ///
+    /// 見つからない場合と所有者でない場合は、`talking_channel_id`にエラーを投稿して`None`を返す
+    async fn find_owned_webhook<I>(
+        &self,
+        infra: &I,
+        webhook_id: &WebhookId,
+        user: &User,
+        talking_channel_id: &ChannelId,
+    ) -> Result<Option<domain::Webhook>, Failure>
+    where
+        I: Infra,
+    {
+        let client = infra.traq_client();
+
+        let webhook = match infra.repo().find_webhook(webhook_id).await {
+            Ok(w) => w,
+            Err(Failure::Reject(r)) => {
+                let message = format!("エラー: {r}");
+                client
+                    .send_message(talking_channel_id, &message, true)
+                    .await?;
+                return Ok(None);
+            }
+            Err(e) => return Err(e),
+        };
+        let own_users_contain_self = webhook.owner.iter_users().any(|u| u.id == user.id);
+        if !own_users_contain_self {
+            let message = format!(
+                "エラー: webhook所有者に @{name} が含まれていません",
+                name = user.name,
+            );
+            client
+                .send_message(talking_channel_id, &message, true)
+                .await?;
+            return Ok(None);
+        }
+        Ok(Some(webhook))
+    }
+}
+
+/// Webhookの投稿先の表示。DMの場合は`DM`
+async fn destination_path<C>(client: &C, webhook: &domain::Webhook) -> Result<String, Failure>
+where
+    C: TraqClient,
+{
+    // `Webhook::destination`はチャンネルかDMのどちらか
+    if let Destination::Channel(channel_id) = webhook.destination() {
+        client.get_channel_path(&channel_id).await
+    } else {
+        Ok(String::from("DM"))
+    }
+}
+
+/// 経過時間を`3分前`のように表示する
+fn elapsed_str(secs: u64) -> String {
+    match secs {
+        0..60 => format!("{secs}秒前"),
+        60..3600 => format!("{}分前", secs / 60),
+        3600..86400 => format!("{}時間前", secs / 3600),
+        _ => format!("{}日前", secs / 86400),
+    }
+}
+
+/// `max_chars`文字を超える場合は末尾を切り詰める
+fn truncate_chars(s: &str, max_chars: usize) -> String {
+    match s.char_indices().nth(max_chars) {
+        Some((end, _)) => format!("{}\n(truncated)", &s[..end]),
+        None => s.to_string(),
+    }
 }
@@ -1,19 +1,127 @@
 use http::HeaderMap;
 
-use domain::{Failure, Infra, Webhook};
+use domain::{
+    Delivery, DeliveryId, DeliveryStatus, Event, EventSubscriber, Failure, Infra, Repository,
+    Webhook,
+};
 use usecases::{WebhookHandler, WebhookKind};
 
 use crate::WebhookHandlerImpl;
 
+mod alertmanager;
+mod backlog;
 mod clickup;
+mod container_registry;
+mod discord;
+mod email;
+mod generic;
 mod gitea;
 mod github;
+mod grafana;
+mod jenkins;
+mod jira;
+mod linear;
+mod netlify;
+mod routing;
+mod sentry;
+mod slack;
+mod stripe;
+mod text;
+mod uptime;
 mod utils;
+mod vercel;
+
+/// 各サービスのハンドラが作ったイベント
+#[must_use]
+#[derive(Debug, Clone)]
+pub(crate) struct Handled {
+    pub(crate) event: Event,
+    /// 投稿先のフィルタ・振り分けに使うイベントの種類 (`push`, `invoice.paid`など)
+    pub(crate) event_type: String,
+}
 
 impl WebhookHandlerImpl {
     pub fn new() -> Self {
         Self
     }
+
+    /// 投稿するイベントがあれば、その種類と投稿先ごとのイベントを返す
+    fn dispatch(
+        &self,
+        kind: WebhookKind,
+        webhook: &Webhook,
+        headers: HeaderMap,
+        payload: &str,
+        delivery_id: DeliveryId,
+    ) -> Result<Option<(String, Vec<Event>)>, Failure> {
+        let handled = match kind {
+            WebhookKind::Clickup => self.handle_clickup(webhook, headers, payload)?,
+            WebhookKind::GitHub => self.handle_github(webhook, headers, payload)?,
+            WebhookKind::Gitea => self.handle_gitea(webhook, headers, payload)?,
+            WebhookKind::Sentry => self.handle_sentry(webhook, headers, payload)?,
+            WebhookKind::Alertmanager => self.handle_alertmanager(webhook, headers, payload)?,
+            WebhookKind::Grafana => self.handle_grafana(webhook, headers, payload)?,
+            WebhookKind::Slack => self.handle_slack(webhook, headers, payload)?,
+            WebhookKind::Discord => self.handle_discord(webhook, headers, payload)?,
+            WebhookKind::Generic => self.handle_generic(webhook, headers, payload)?,
+            WebhookKind::Text => self.handle_text(webhook, headers, payload)?,
+            WebhookKind::Jira => self.handle_jira(webhook, headers, payload)?,
+            WebhookKind::Linear => self.handle_linear(webhook, headers, payload)?,
+            WebhookKind::Backlog => self.handle_backlog(webhook, headers, payload)?,
+            WebhookKind::ContainerRegistry => {
+                self.handle_container_registry(webhook, headers, payload)?
+            }
+            WebhookKind::Uptime => self.handle_uptime(webhook, headers, payload)?,
+            WebhookKind::Jenkins => self.handle_jenkins(webhook, headers, payload)?,
+            WebhookKind::Vercel => self.handle_vercel(webhook, headers, payload)?,
+            WebhookKind::Netlify => self.handle_netlify(webhook, headers, payload)?,
+            WebhookKind::Stripe => self.handle_stripe(webhook, headers, payload)?,
+            WebhookKind::Email => self.handle_email(webhook, headers, payload)?,
+        };
+        let Some(Handled { event, event_type }) = handled else {
+            return Ok(None);
+        };
+        let primary = if webhook.rules.is_empty() {
+            webhook.destination()
+        } else {
+            let facts = routing::Facts::new(&event_type, payload);
+            webhook.route(|field| facts.values(field))
+        };
+        // 追加の投稿先にはフィルタに一致するイベントだけを流す
+        let events = webhook
+            .destinations(primary, &event_type)
+            .map(|destination| Event {
+                destination,
+                deliveries: vec![delivery_id],
+                ..event.clone()
+            })
+            .collect();
+        Ok(Some((event_type, events)))
+    }
+
+    /// 一部の投稿先に追加できていれば、再送で重複して投稿されないよう成功として扱う
+    async fn queue<I>(infra: &I, events: Vec<Event>) -> Result<(), Failure>
+    where
+        I: Infra,
+    {
+        let subscriber = infra.event_subscriber();
+        let mut queued = false;
+        let mut failure = None;
+        for event in events {
+            let destination = event.destination().clone();
+            match subscriber.send(event).await {
+                Ok(()) => queued = true,
+                Err(e) => {
+                    tracing::error!(%destination, error = ?e, "failed to queue event");
+                    failure = Some(e);
+                }
+            }
+        }
+        match failure {
+            Some(e) if !queued => Err(e),
+            _ => Ok(()),
+        }
+    }
 }
 
 impl Default for WebhookHandlerImpl {
@@ -34,17 +142,40 @@ where
         headers: HeaderMap,
         payload: &str,
     ) -> Result<(), Failure> {
-        match kind {
-            WebhookKind::Clickup => {
-                self.handle_clickup(infra, webhook, headers, payload)
-                    .await?;
+        let delivery = Delivery::new(webhook.id, kind.as_str(), payload)
+            .with_event(utils::event_type(&headers))
+            .with_remote_id(utils::delivery_id(&headers));
+        let res = self.dispatch(kind, &webhook, headers, payload, delivery.id);
+        let delivery = match &res {
+            Ok(Some((event_type, _))) => delivery
+                .with_event(Some(event_type.clone()))
+                .with_status(DeliveryStatus::Accepted, None),
+            Ok(None) => delivery.with_status(DeliveryStatus::Filtered, None),
+            Err(e @ Failure::Reject(_)) => {
+                delivery.with_status(DeliveryStatus::Rejected, Some(e.to_string()))
             }
-            WebhookKind::GitHub => {
-                self.handle_github(infra, webhook, headers, payload).await?;
+            Err(e @ Failure::Error(_)) => {
+                delivery.with_status(DeliveryStatus::Error, Some(e.to_string()))
             }
-            WebhookKind::Gitea => {
-                self.handle_gitea(infra, webhook, headers, payload).await?;
+        };
+        // traQへの投稿に失敗したときに記録を更新できるよう、イベントを流す前に記録する
+        // 記録に失敗してもWebhookの処理結果は変えない
+        let repo = infra.repo();
+        if let Err(e) = repo.add_delivery(&delivery).await {
+            tracing::warn!("failed to record delivery: {e}");
+        }
+        let Some((_, events)) = res? else {
+            return Ok(());
+        };
+        if let Err(e) = Self::queue(infra, events).await {
+            let error = e.to_string();
+            let res = repo
+                .update_delivery_status(&delivery.id, DeliveryStatus::Error, Some(&error))
+                .await;
+            if let Err(e) = res {
+                tracing::warn!("failed to record delivery: {e}");
             }
+            return Err(e);
         }
         Ok(())
     }
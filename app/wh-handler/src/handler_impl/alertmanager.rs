@@ -0,0 +1,207 @@
+use std::collections::BTreeMap;
+
+use http::HeaderMap;
+use indoc::formatdoc;
+use itertools::Itertools;
+use serde::Deserialize;
+
+use domain::{Event, Failure, Webhook};
+
+use super::Handled;
+use super::utils::parse_payload;
+use crate::WebhookHandlerImpl;
+
+/// これを超える数のアラートが一度に来たら要約する
+const MAX_DETAILED_ALERTS: usize = 5;
+
+impl WebhookHandlerImpl {
+    pub(crate) fn handle_alertmanager(
+        &self,
+        webhook: &Webhook,
+        headers: HeaderMap,
+        payload: &str,
+    ) -> Result<Option<Handled>, Failure> {
+        let Some((group_key, event_type, message)) = handle(headers, payload)? else {
+            return Ok(None);
+        };
+        // 同じグループの通知はnotifierでまとめられる
+        let kind = format!("alertmanager:{group_key}").into();
+        let event = Event {
+            destination: webhook.destination(),
+            kind,
+            body: message.into(),
+            collapse: false,
+            deliveries: vec![],
+        };
+        Ok(Some(Handled { event, event_type }))
+    }
+}
+
+/// <https://prometheus.io/docs/alerting/latest/configuration/#webhook_config>
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Payload {
+    version: String,
+    group_key: String,
+    #[serde(default)]
+    truncated_alerts: usize,
+    status: Status,
+    receiver: String,
+    #[serde(default)]
+    group_labels: BTreeMap<String, String>,
+    alerts: Vec<Alert>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Status {
+    Firing,
+    Resolved,
+}
+
+impl Status {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Firing => "firing",
+            Self::Resolved => "resolved",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Alert {
+    status: Status,
+    #[serde(default)]
+    labels: BTreeMap<String, String>,
+    #[serde(default)]
+    annotations: BTreeMap<String, String>,
+    starts_at: String,
+    ends_at: String,
+    #[serde(rename = "generatorURL")]
+    generator_url: String,
+}
+
+/// グループのキー・イベントの種類 (`firing`, `resolved`)・メッセージを返す
+#[tracing::instrument(target = "wh_handler::alertmanager::handle", skip_all)]
+fn handle(_headers: HeaderMap, payload: &str) -> Result<Option<(String, String, String)>, Failure> {
+    let payload: Payload = parse_payload(payload)?;
+    if payload.version != "4" {
+        let message = format!(
+            "unsupported alertmanager webhook version: {}",
+            payload.version
+        );
+        return Err(Failure::reject_bad_request(message));
+    }
+    tracing::info!(
+        group_key = payload.group_key,
+        "alertmanager: {:?}",
+        payload.status
+    );
+    if payload.alerts.is_empty() {
+        return Ok(None);
+    }
+    let message = group_str(&payload);
+    Ok(Some((
+        payload.group_key,
+        payload.status.as_str().to_string(),
+        message,
+    )))
+}
+
+fn group_str(payload: &Payload) -> String {
+    let Payload {
+        truncated_alerts,
+        status,
+        receiver,
+        group_labels,
+        alerts,
+        ..
+    } = payload;
+    let (firing, resolved): (Vec<_>, Vec<_>) =
+        alerts.iter().partition(|a| a.status == Status::Firing);
+    let headline_icon = match status {
+        Status::Firing => ":fire:",
+        Status::Resolved => ":white_check_mark:",
+    };
+    let group = labels_str(group_labels.iter());
+    let headline = format!(
+        "[{receiver}] {headline_icon} {firing} firing / {resolved} resolved {group}",
+        firing = firing.len(),
+        resolved = resolved.len()
+    );
+    let detailed = alerts.len() <= MAX_DETAILED_ALERTS;
+    let sections = [
+        (":fire: Firing", &firing),
+        (":white_check_mark: Resolved", &resolved),
+    ]
+    .into_iter()
+    .filter(|(_, alerts)| !alerts.is_empty())
+    .map(|(title, alerts)| {
+        let body = if detailed {
+            alerts.iter().map(|a| alert_str(a)).join("\n")
+        } else {
+            summary_str(alerts)
+        };
+        format!("#### {title}\n{body}")
+    })
+    .join("\n");
+    let truncated = if *truncated_alerts > 0 {
+        format!("\n({truncated_alerts} alerts truncated)")
+    } else {
+        String::new()
+    };
+    format!("{headline}\n{sections}{truncated}\n")
+}
+
+fn alert_str(alert: &Alert) -> String {
+    let Alert {
+        status,
+        labels,
+        annotations,
+        starts_at,
+        ends_at,
+        generator_url,
+    } = alert;
+    let name = labels.get("alertname").map_or("alert", String::as_str);
+    let summary = annotations.get("summary").map_or("", String::as_str);
+    let labels = labels_str(labels.iter().filter(|(k, _)| *k != "alertname"));
+    let period = match status {
+        Status::Firing => format!("since {starts_at}"),
+        Status::Resolved => format!("{starts_at} - {ends_at}"),
+    };
+    let description = annotations
+        .get("description")
+        .map(|d| format!("\n    {}", d.lines().join("\n    ")))
+        .unwrap_or_default();
+    formatdoc! {
+        r"
+            - **{name}** {summary} ([source]({generator_url}))
+                {labels} {period}{description}
+        "
+    }
+    .trim_end()
+    .to_string()
+}
+
+/// `alertname`ごとの件数だけ並べる
+fn summary_str(alerts: &[&Alert]) -> String {
+    alerts
+        .iter()
+        .map(|a| a.labels.get("alertname").map_or("alert", String::as_str))
+        .counts()
+        .into_iter()
+        .sorted()
+        .map(|(name, count)| format!("- **{name}** x{count}"))
+        .join("\n")
+}
+
+fn labels_str<'a, I>(labels: I) -> String
+where
+    I: IntoIterator<Item = (&'a String, &'a String)>,
+{
+    labels
+        .into_iter()
+        .map(|(k, v)| format!("`{k}={v}`"))
+        .join(" ")
+}
@@ -0,0 +1,335 @@
+use http::HeaderMap;
+use indoc::formatdoc;
+use itertools::Itertools;
+use serde::Deserialize;
+use serde_json::Value;
+
+use domain::{Event, Failure, Webhook};
+
+use super::Handled;
+use super::utils::{parse_payload, parse_value};
+use crate::WebhookHandlerImpl;
+
+impl WebhookHandlerImpl {
+    pub(crate) fn handle_backlog(
+        &self,
+        webhook: &Webhook,
+        headers: HeaderMap,
+        payload: &str,
+    ) -> Result<Option<Handled>, Failure> {
+        let Some((event_type, message)) = handle(headers, payload)? else {
+            return Ok(None);
+        };
+        let kind = "backlog".to_string().into();
+        let event = Event {
+            destination: webhook.destination(),
+            kind,
+            body: message.into(),
+            collapse: false,
+            deliveries: vec![],
+        };
+        Ok(Some(Handled { event, event_type }))
+    }
+}
+
+/// <https://developer.nulab.com/docs/backlog/api/2/get-recent-updates/#response-description>
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Payload {
+    project: Project,
+    r#type: u32,
+    content: Value,
+    created_user: Option<User>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Project {
+    project_key: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct User {
+    name: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Issue {
+    key_id: u64,
+    summary: String,
+    comment: Option<Comment>,
+    #[serde(default)]
+    changes: Vec<Change>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Comment {
+    content: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Change {
+    field: String,
+    new_value: Option<String>,
+    old_value: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Wiki {
+    id: u64,
+    name: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct File {
+    dir: String,
+    name: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct GitPush {
+    repository: Repository,
+    r#ref: String,
+    #[serde(default)]
+    revisions: Vec<Revision>,
+    revision_count: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Repository {
+    name: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Revision {
+    rev: String,
+    comment: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct PullRequest {
+    repository: Repository,
+    number: u64,
+    summary: String,
+    base: Option<String>,
+    branch: Option<String>,
+    comment: Option<Comment>,
+    #[serde(default)]
+    changes: Vec<Change>,
+}
+
+/// イベントの種類とメッセージを返す
+#[tracing::instrument(target = "wh_handler::backlog::handle", skip_all)]
+fn handle(headers: HeaderMap, payload: &str) -> Result<Option<(String, String)>, Failure> {
+    // 通知にスペースのURLは含まれないので、routerが`?space=`を`X-Backlog-Space`ヘッダーに移している
+    let space = headers
+        .get("X-Backlog-Space")
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.trim_end_matches('/').to_string());
+    let Payload {
+        project,
+        r#type,
+        content,
+        created_user,
+    } = parse_payload(payload)?;
+    tracing::info!("Backlog type: {type}");
+    let ctx = Context {
+        space,
+        project,
+        user: created_user.map_or_else(|| "someone".to_string(), |u| u.name),
+    };
+    let (event_type, message) = match r#type {
+        1 => ("issue_created", issue(&ctx, content, "created")?),
+        2 => ("issue_updated", issue(&ctx, content, "updated")?),
+        3 => ("issue_commented", issue(&ctx, content, "commented")?),
+        5 => ("wiki_created", wiki(&ctx, content, "created")?),
+        6 => ("wiki_updated", wiki(&ctx, content, "updated")?),
+        8 => ("file_added", file_added(&ctx, content)?),
+        12 => ("git_pushed", git_pushed(&ctx, content)?),
+        18 => (
+            "pull_request_created",
+            pull_request(&ctx, content, "created")?,
+        ),
+        19 => (
+            "pull_request_updated",
+            pull_request(&ctx, content, "updated")?,
+        ),
+        20 => (
+            "pull_request_commented",
+            pull_request(&ctx, content, "commented")?,
+        ),
+        _ => return Ok(None),
+    };
+    Ok(Some((event_type.to_string(), message)))
+}
+
+struct Context {
+    space: Option<String>,
+    project: Project,
+    user: String,
+}
+
+impl Context {
+    /// スペースが分からない場合はリンクにしない
+    fn link(&self, text: &str, path: &str) -> String {
+        match &self.space {
+            Some(space) => format!("[{text}](https://{space}{path})"),
+            None => text.to_string(),
+        }
+    }
+
+    fn project_str(&self) -> String {
+        let key = &self.project.project_key;
+        self.link(key, &format!("/projects/{key}"))
+    }
+}
+
+/// `type`: 1, 2, 3
+fn issue(ctx: &Context, content: Value, action: &str) -> Result<String, Failure> {
+    let Issue {
+        key_id,
+        summary,
+        comment,
+        changes,
+    } = parse_value(content)?;
+    let key = format!("{}-{key_id}", ctx.project.project_key);
+    let issue = ctx.link(&format!("{key} {summary}"), &format!("/view/{key}"));
+    let message = formatdoc! {
+        r"
+            [{project}] Issue {issue} {action} by {user}
+            {changes}
+            {comment}
+        ",
+        project = ctx.project_str(),
+        user = ctx.user,
+        changes = changes_str(&changes),
+        comment = comment_str(comment.as_ref()),
+    };
+    Ok(trim_blank_lines(&message))
+}
+
+/// `type`: 5, 6
+fn wiki(ctx: &Context, content: Value, action: &str) -> Result<String, Failure> {
+    let Wiki { id, name } = parse_value(content)?;
+    let message = format!(
+        "[{project}] Wiki {wiki} {action} by {user}\n",
+        project = ctx.project_str(),
+        wiki = ctx.link(&name, &format!("/alias/wiki/{id}")),
+        user = ctx.user,
+    );
+    Ok(message)
+}
+
+/// `type`: 8
+fn file_added(ctx: &Context, content: Value) -> Result<String, Failure> {
+    let File { dir, name } = parse_value(content)?;
+    let key = &ctx.project.project_key;
+    let message = format!(
+        "[{project}] File {file} added by {user}\n",
+        project = ctx.project_str(),
+        file = ctx.link(&format!("{dir}{name}"), &format!("/file/{key}{dir}{name}")),
+        user = ctx.user,
+    );
+    Ok(message)
+}
+
+/// `type`: 12
+fn git_pushed(ctx: &Context, content: Value) -> Result<String, Failure> {
+    let GitPush {
+        repository,
+        r#ref,
+        revisions,
+        revision_count,
+    } = parse_value(content)?;
+    let key = &ctx.project.project_key;
+    let repo = &repository.name;
+    let branch = r#ref.strip_prefix("refs/heads/").unwrap_or(&r#ref);
+    let count = revision_count.unwrap_or(revisions.len() as u64);
+    let revisions = revisions
+        .iter()
+        .map(|Revision { rev, comment }| {
+            let short = rev.get(..7).unwrap_or(rev);
+            let rev = ctx.link(
+                &format!("`{short}`"),
+                &format!("/git/{key}/{repo}/commit/{rev}"),
+            );
+            let comment = comment.lines().next().unwrap_or_default();
+            format!("- {rev} {comment}")
+        })
+        .join("\n");
+    let message = formatdoc! {
+        r"
+            [{project}] {count} commit(s) pushed to {branch} by {user}
+            {revisions}
+        ",
+        project = ctx.project_str(),
+        branch = ctx.link(&format!("{repo}:{branch}"), &format!("/git/{key}/{repo}/tree/{branch}")),
+        user = ctx.user,
+    };
+    Ok(message)
+}
+
+/// `type`: 18, 19, 20
+fn pull_request(ctx: &Context, content: Value, action: &str) -> Result<String, Failure> {
+    let PullRequest {
+        repository,
+        number,
+        summary,
+        base,
+        branch,
+        comment,
+        changes,
+    } = parse_value(content)?;
+    let key = &ctx.project.project_key;
+    let repo = &repository.name;
+    let pr = ctx.link(
+        &format!("{repo}#{number} {summary}"),
+        &format!("/git/{key}/{repo}/pullRequests/{number}"),
+    );
+    let branches = match (base, branch) {
+        (Some(base), Some(branch)) if action == "created" => format!("{branch} → {base}"),
+        _ => String::new(),
+    };
+    let message = formatdoc! {
+        r"
+            [{project}] Pull request {pr} {action} by {user}
+            {branches}
+            {changes}
+            {comment}
+        ",
+        project = ctx.project_str(),
+        user = ctx.user,
+        changes = changes_str(&changes),
+        comment = comment_str(comment.as_ref()),
+    };
+    Ok(trim_blank_lines(&message))
+}
+
+fn changes_str(changes: &[Change]) -> String {
+    changes
+        .iter()
+        .map(|c| {
+            let old = c.old_value.as_deref().filter(|v| !v.is_empty());
+            let new = c.new_value.as_deref().filter(|v| !v.is_empty());
+            format!(
+                "- {}: {} → {}",
+                c.field,
+                old.unwrap_or("(none)"),
+                new.unwrap_or("(none)")
+            )
+        })
+        .join("\n")
+}
+
+fn comment_str(comment: Option<&Comment>) -> String {
+    comment
+        .and_then(|c| c.content.as_deref())
+        .map(|c| c.lines().map(|l| format!("> {l}")).join("\n"))
+        .unwrap_or_default()
+}
+
+/// 空の項目による空行を取り除く
+fn trim_blank_lines(message: &str) -> String {
+    let lines = message.lines().filter(|l| !l.is_empty()).join("\n");
+    format!("{lines}\n")
+}
@@ -2,40 +2,38 @@ use http::HeaderMap;
 use indoc::formatdoc;
 use serde_json::Value;
 
-use domain::{Event, EventSubscriber, Failure, Infra, Webhook};
+use domain::{Event, Failure, Webhook};
 
-use super::utils::ValueExt;
+use super::Handled;
+use super::utils::{ValueExt, parse_payload};
 use crate::WebhookHandlerImpl;
 
 impl WebhookHandlerImpl {
-    pub(crate) async fn handle_clickup<I>(
+    pub(crate) fn handle_clickup(
         &self,
-        infra: &I,
-        webhook: Webhook,
+        webhook: &Webhook,
         headers: HeaderMap,
         payload: &str,
-    ) -> Result<(), Failure>
-    where
-        I: Infra,
-    {
-        let subscriber = infra.event_subscriber();
-        let Some(message) = handle(headers, payload)? else {
-            return Ok(());
+    ) -> Result<Option<Handled>, Failure> {
+        let Some((event_type, message)) = handle(headers, payload)? else {
+            return Ok(None);
         };
-        let kind = "clickup".to_string().into(); // TODO: event_type
+        let kind = "clickup".to_string().into();
         let event = Event {
-            channel_id: webhook.channel_id,
+            destination: webhook.destination(),
             kind,
             body: message.into(),
+            collapse: false,
+            deliveries: vec![],
         };
-        subscriber.send(event).await?;
-        Ok(())
+        Ok(Some(Handled { event, event_type }))
     }
 }
 
+/// イベントの種類とメッセージを返す
 #[tracing::instrument(target = "wh_handler::gitea::handle", skip_all)]
-fn handle(_headers: HeaderMap, payload: &str) -> Result<Option<String>, Failure> {
-    let payload: Value = serde_json::from_str(payload).map_err(anyhow::Error::from)?;
+fn handle(_headers: HeaderMap, payload: &str) -> Result<Option<(String, String)>, Failure> {
+    let payload: Value = parse_payload(payload)?;
     let event = payload.get_or_err("event")?.as_str_or_err()?;
     tracing::info!("clickup event: {}", event);
     let message = formatdoc! {
@@ -45,5 +43,5 @@ fn handle(_headers: HeaderMap, payload: &str) -> Result<Option<String>, Failure>
             実装は現在工事中です :construction:
         "#,
     };
-    Ok(Some(message))
+    Ok(Some((event.to_string(), message)))
 }
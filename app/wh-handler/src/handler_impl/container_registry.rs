@@ -0,0 +1,297 @@
+use std::str::from_utf8;
+
+use http::HeaderMap;
+use indoc::formatdoc;
+use itertools::Itertools;
+use serde::Deserialize;
+
+use domain::{Event, Failure, Webhook};
+
+use super::Handled;
+use super::utils::parse_payload;
+use crate::WebhookHandlerImpl;
+
+impl WebhookHandlerImpl {
+    pub(crate) fn handle_container_registry(
+        &self,
+        webhook: &Webhook,
+        headers: HeaderMap,
+        payload: &str,
+    ) -> Result<Option<Handled>, Failure> {
+        let Some((event_type, message)) = handle(headers, payload)? else {
+            return Ok(None);
+        };
+        let kind = "container_registry".to_string().into();
+        let event = Event {
+            destination: webhook.destination(),
+            kind,
+            body: message.into(),
+            collapse: false,
+            deliveries: vec![],
+        };
+        Ok(Some(Handled { event, event_type }))
+    }
+}
+
+/// 送信元ごとに形式が異なるので、フィールドの有無で判別する
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum Payload {
+    Distribution(DistributionPayload),
+    DockerHub(DockerHubPayload),
+}
+
+/// <https://distribution.github.io/distribution/about/notifications/>
+#[derive(Debug, Clone, Deserialize)]
+struct DistributionPayload {
+    events: Vec<DistributionEvent>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct DistributionEvent {
+    action: String,
+    target: DistributionTarget,
+    request: Option<DistributionRequest>,
+    actor: Option<DistributionActor>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DistributionTarget {
+    media_type: Option<String>,
+    digest: Option<String>,
+    repository: String,
+    tag: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct DistributionRequest {
+    host: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct DistributionActor {
+    name: Option<String>,
+}
+
+/// <https://docs.docker.com/docker-hub/repos/manage/webhooks/>
+#[derive(Debug, Clone, Deserialize)]
+struct DockerHubPayload {
+    push_data: DockerHubPushData,
+    repository: DockerHubRepository,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct DockerHubPushData {
+    pusher: String,
+    tag: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct DockerHubRepository {
+    repo_name: String,
+    repo_url: String,
+}
+
+/// <https://docs.github.com/en/webhooks/webhook-events-and-payloads#package>
+#[derive(Debug, Clone, Deserialize)]
+struct GitHubPackagePayload {
+    action: String,
+    #[serde(alias = "registry_package")]
+    package: GitHubPackage,
+    repository: Option<GitHubRepository>,
+    sender: GitHubUser,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct GitHubPackage {
+    name: String,
+    namespace: Option<String>,
+    html_url: String,
+    package_version: Option<GitHubPackageVersion>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct GitHubPackageVersion {
+    version: Option<String>,
+    html_url: Option<String>,
+    container_metadata: Option<GitHubContainerMetadata>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct GitHubContainerMetadata {
+    tag: Option<GitHubContainerTag>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct GitHubContainerTag {
+    name: Option<String>,
+    digest: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct GitHubRepository {
+    full_name: String,
+    html_url: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct GitHubUser {
+    login: String,
+}
+
+/// イベントの種類とメッセージを返す
+#[tracing::instrument(target = "wh_handler::container_registry::handle", skip_all)]
+fn handle(headers: HeaderMap, payload: &str) -> Result<Option<(String, String)>, Failure> {
+    // GitHubの`package`, `registry_package`イベント
+    if let Some(event_type) = headers.get("X-GitHub-Event") {
+        let event_type = from_utf8(event_type.as_bytes()).unwrap_or_default();
+        tracing::info!("X-GitHub-Event: {event_type}");
+        if !matches!(event_type, "package" | "registry_package") {
+            return Ok(None);
+        }
+        let payload = parse_payload(payload)?;
+        return Ok(Some((event_type.to_string(), github_package(payload))));
+    }
+    // Docker HubとDistributionはpushだけを通知する
+    let message = match parse_payload(payload)? {
+        Payload::Distribution(payload) => distribution(payload),
+        Payload::DockerHub(payload) => Some(docker_hub(payload)),
+    };
+    Ok(message.map(|m| ("push".to_string(), m)))
+}
+
+/// Docker Hubのrepository push webhook
+fn docker_hub(payload: DockerHubPayload) -> String {
+    let DockerHubPayload {
+        push_data: DockerHubPushData { pusher, tag },
+        repository: DockerHubRepository {
+            repo_name,
+            repo_url,
+        },
+    } = payload;
+    format!("[Docker Hub] :whale: [{repo_name}:{tag}]({repo_url}) was pushed by {pusher}\n")
+}
+
+/// Distribution (registry v2) のnotification
+///
+/// 1つのリクエストに複数のイベントが含まれるので、マニフェストのpushのみまとめて通知する
+fn distribution(payload: DistributionPayload) -> Option<String> {
+    let pushes = payload
+        .events
+        .iter()
+        .filter(|e| e.action == "push")
+        .filter(|e| {
+            e.target.tag.is_some()
+                || e.target
+                    .media_type
+                    .as_deref()
+                    .is_some_and(|m| m.contains("manifest"))
+        })
+        .collect_vec();
+    if pushes.is_empty() {
+        return None;
+    }
+    let host = pushes
+        .iter()
+        .find_map(|e| e.request.as_ref()?.host.as_deref())
+        .unwrap_or("registry");
+    let images = pushes
+        .iter()
+        .map(|DistributionEvent { target, actor, .. }| {
+            let DistributionTarget {
+                digest,
+                repository,
+                tag,
+                ..
+            } = target;
+            let image = match tag {
+                Some(tag) => format!("{repository}:{tag}"),
+                None => repository.clone(),
+            };
+            let digest = digest.as_deref().map(digest_str).unwrap_or_default();
+            let pusher = actor.as_ref().and_then(|a| a.name.as_deref());
+            let pusher = pusher.map(|p| format!(" by {p}")).unwrap_or_default();
+            format!("- `{image}` {digest}{pusher}")
+        })
+        .unique()
+        .collect_vec();
+    let message = formatdoc! {
+        r"
+            [{host}] :whale: {count} image(s) pushed
+            {images}
+        ",
+        count = images.len(),
+        images = images.join("\n"),
+    };
+    Some(message)
+}
+
+/// `X-GitHub-Event: package`, `registry_package`
+fn github_package(payload: GitHubPackagePayload) -> String {
+    let GitHubPackagePayload {
+        action,
+        package,
+        repository,
+        sender,
+    } = payload;
+    let GitHubPackage {
+        name,
+        namespace,
+        html_url,
+        package_version,
+    } = package;
+    let prefix = match (&repository, &namespace) {
+        (
+            Some(GitHubRepository {
+                full_name,
+                html_url,
+            }),
+            _,
+        ) => format!("[{full_name}]({html_url})"),
+        (None, Some(namespace)) => namespace.clone(),
+        (None, None) => "GitHub".to_string(),
+    };
+    let version = package_version.as_ref();
+    let tag = version
+        .and_then(|v| v.container_metadata.as_ref()?.tag.as_ref())
+        .and_then(|t| t.name.as_deref().filter(|n| !n.is_empty()))
+        .or_else(|| version.and_then(|v| v.version.as_deref()));
+    let image = match tag {
+        Some(tag) => format!("{name}:{tag}"),
+        None => name,
+    };
+    let url = version
+        .and_then(|v| v.html_url.as_deref())
+        .unwrap_or(&html_url);
+    let digest = version
+        .and_then(|v| {
+            v.container_metadata
+                .as_ref()?
+                .tag
+                .as_ref()?
+                .digest
+                .as_deref()
+        })
+        .map(digest_str)
+        .unwrap_or_default();
+    let message = formatdoc! {
+        r"
+            [{prefix}] :package: Package [{image}]({url}) was {action} by {sender}
+            {digest}
+        ",
+        sender = sender.login,
+    };
+    message
+}
+
+/// `sha256:`と先頭12文字だけ表示する
+fn digest_str(digest: &str) -> String {
+    let (algorithm, hash) = digest.split_once(':').unwrap_or(("", digest));
+    let hash = hash.get(..12).unwrap_or(hash);
+    if algorithm.is_empty() {
+        format!("`{hash}`")
+    } else {
+        format!("`{algorithm}:{hash}`")
+    }
+}
@@ -0,0 +1,172 @@
+use http::HeaderMap;
+use itertools::Itertools;
+use serde::Deserialize;
+
+use domain::{Event, Failure, Webhook};
+
+use super::Handled;
+use super::utils::{OptionExt, color_emoji, parse_payload};
+use crate::WebhookHandlerImpl;
+
+impl WebhookHandlerImpl {
+    pub(crate) fn handle_discord(
+        &self,
+        webhook: &Webhook,
+        headers: HeaderMap,
+        payload: &str,
+    ) -> Result<Option<Handled>, Failure> {
+        let Some(message) = handle(headers, payload)? else {
+            return Ok(None);
+        };
+        let event_type = "discord".to_string();
+        let kind = event_type.clone().into();
+        let event = Event {
+            destination: webhook.destination(),
+            kind,
+            body: message.into(),
+            collapse: false,
+            deliveries: vec![],
+        };
+        Ok(Some(Handled { event, event_type }))
+    }
+}
+
+/// <https://discord.com/developers/docs/resources/webhook#execute-webhook>
+#[derive(Debug, Clone, Deserialize)]
+struct Payload {
+    content: Option<String>,
+    username: Option<String>,
+    #[serde(default)]
+    embeds: Vec<Embed>,
+}
+
+/// <https://discord.com/developers/docs/resources/message#embed-object>
+#[derive(Debug, Clone, Deserialize)]
+struct Embed {
+    title: Option<String>,
+    description: Option<String>,
+    url: Option<String>,
+    timestamp: Option<String>,
+    color: Option<u32>,
+    footer: Option<EmbedFooter>,
+    image: Option<EmbedMedia>,
+    author: Option<EmbedAuthor>,
+    #[serde(default)]
+    fields: Vec<EmbedField>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct EmbedFooter {
+    text: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct EmbedMedia {
+    url: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct EmbedAuthor {
+    name: String,
+    url: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct EmbedField {
+    name: String,
+    value: String,
+}
+
+#[tracing::instrument(target = "wh_handler::discord::handle", skip_all)]
+fn handle(headers: HeaderMap, payload: &str) -> Result<Option<String>, Failure> {
+    let content_type = headers
+        .get(http::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    let payload = if content_type.starts_with("multipart/form-data") {
+        extract_payload_json(content_type, payload).ok_or_err()?
+    } else {
+        payload
+    };
+    let payload: Payload = parse_payload(payload)?;
+    let Payload {
+        content,
+        username,
+        embeds,
+    } = payload;
+    let message = username
+        .map(|u| format!("[{u}]"))
+        .into_iter()
+        .chain(content)
+        .chain(embeds.iter().map(embed_str))
+        .filter(|s| !s.trim().is_empty())
+        .join("\n");
+    if message.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(message))
+}
+
+/// `multipart/form-data`のうち`payload_json`パートの中身を取り出す
+///
+/// 添付ファイルのパートは無視する
+fn extract_payload_json<'a>(content_type: &str, payload: &'a str) -> Option<&'a str> {
+    let boundary = content_type
+        .split(';')
+        .find_map(|p| p.trim().strip_prefix("boundary="))?
+        .trim_matches('"');
+    let delimiter = format!("--{boundary}");
+    payload.split(delimiter.as_str()).find_map(|part| {
+        let (part_headers, body) = part.split_once("\r\n\r\n")?;
+        let is_payload_json = part_headers.lines().any(|l| {
+            l.to_ascii_lowercase().starts_with("content-disposition")
+                && l.contains("name=\"payload_json\"")
+        });
+        is_payload_json.then(|| body.strip_suffix("\r\n").unwrap_or(body))
+    })
+}
+
+fn embed_str(embed: &Embed) -> String {
+    let Embed {
+        title,
+        description,
+        url,
+        timestamp,
+        color,
+        footer,
+        image,
+        author,
+        fields,
+    } = embed;
+    let author = author.as_ref().map(|EmbedAuthor { name, url }| match url {
+        Some(url) => format!("[{name}]({url})"),
+        None => name.clone(),
+    });
+    let title = title.as_deref().map(|title| match url {
+        Some(url) => format!("**[{title}]({url})**"),
+        None => format!("**{title}**"),
+    });
+    let headline = color
+        .map(color_emoji)
+        .map(str::to_string)
+        .into_iter()
+        .chain(author)
+        .chain(title)
+        .join(" ");
+    let fields = fields
+        .iter()
+        .map(|EmbedField { name, value }| format!("- **{name}**: {value}"));
+    let footer = footer
+        .as_ref()
+        .map(|f| f.text.as_str())
+        .into_iter()
+        .chain(timestamp.as_deref())
+        .join(" | ");
+    std::iter::once(headline)
+        .chain(description.clone())
+        .chain(fields)
+        .chain(image.as_ref().map(|i| i.url.clone()))
+        .chain(std::iter::once(footer))
+        .filter(|s| !s.is_empty())
+        .join("\n")
+}
@@ -0,0 +1,310 @@
+use base64::{Engine, engine::general_purpose::STANDARD};
+use http::HeaderMap;
+
+use domain::{Event, Failure, Webhook};
+
+use super::Handled;
+use super::utils::truncate;
+use crate::WebhookHandlerImpl;
+
+/// 本文の最大文字数
+const MAX_BODY_LENGTH: usize = 4000;
+
+impl WebhookHandlerImpl {
+    pub(crate) fn handle_email(
+        &self,
+        webhook: &Webhook,
+        headers: HeaderMap,
+        payload: &str,
+    ) -> Result<Option<Handled>, Failure> {
+        let message = handle(headers, payload)?;
+        let event_type = "email".to_string();
+        let kind = event_type.clone().into();
+        let event = Event {
+            destination: webhook.destination(),
+            kind,
+            body: message.into(),
+            collapse: false,
+            deliveries: vec![],
+        };
+        Ok(Some(Handled { event, event_type }))
+    }
+}
+
+/// ヘッダーと本文に分けたメール (またはmultipartの各パート)
+#[derive(Debug, Clone)]
+struct Part<'a> {
+    headers: Vec<(&'a str, String)>,
+    body: &'a str,
+}
+
+impl<'a> Part<'a> {
+    fn parse(raw: &'a str) -> Self {
+        let (head, body) = raw
+            .split_once("\r\n\r\n")
+            .or_else(|| raw.split_once("\n\n"))
+            .unwrap_or((raw, ""));
+        let mut headers: Vec<(&str, String)> = vec![];
+        for line in head.lines() {
+            // 空白で始まる行は前のヘッダーの続き
+            if line.starts_with([' ', '\t']) {
+                if let Some((_, value)) = headers.last_mut() {
+                    *value += " ";
+                    *value += line.trim();
+                }
+                continue;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                headers.push((name.trim(), value.trim().to_string()));
+            }
+        }
+        Self { headers, body }
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// `payload`はSMTPで受け取ったRFC 5322形式のメール
+#[tracing::instrument(target = "wh_handler::email::handle", skip_all)]
+fn handle(_headers: HeaderMap, payload: &str) -> Result<String, Failure> {
+    let mail = Part::parse(payload);
+    if mail.headers.is_empty() {
+        return Err(Failure::reject_bad_request(
+            "Received unexpected payload: missing mail headers",
+        ));
+    }
+    let subject = mail
+        .header("Subject")
+        .map(decode_words)
+        .filter(|s| !s.trim().is_empty())
+        .unwrap_or_else(|| "(no subject)".to_string());
+    let from = mail
+        .header("From")
+        .map(|f| format!("From: {}\n", decode_words(f)))
+        .unwrap_or_default();
+    tracing::info!("email: {subject}");
+    let body = text_body(&mail).unwrap_or_default();
+    let body = truncate(body.trim().to_string(), MAX_BODY_LENGTH);
+    Ok(format!("### {subject}\n{from}{body}\n"))
+}
+
+/// `text/plain`の本文を取り出す。multipartの場合は最初の`text/plain`のパート
+fn text_body(part: &Part<'_>) -> Option<String> {
+    let content_type = part.header("Content-Type").unwrap_or("text/plain");
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    if mime.starts_with("multipart/") {
+        let boundary = parameter(content_type, "boundary")?;
+        let delimiter = format!("--{boundary}");
+        return part
+            .body
+            .split(delimiter.as_str())
+            // 最初の区切りより前はpreamble、`--`で始まるものは終端
+            .skip(1)
+            .take_while(|p| !p.starts_with("--"))
+            .map(|p| p.trim_start_matches(['\r', '\n']))
+            .find_map(|p| text_body(&Part::parse(p)));
+    }
+    if mime != "text/plain" {
+        return None;
+    }
+    let encoding = part
+        .header("Content-Transfer-Encoding")
+        .unwrap_or("7bit")
+        .to_ascii_lowercase();
+    let bytes = match encoding.as_str() {
+        "base64" => {
+            let encoded: String = part.body.split_whitespace().collect();
+            STANDARD
+                .decode(encoded)
+                .unwrap_or_else(|_| part.body.as_bytes().to_vec())
+        }
+        "quoted-printable" => decode_quoted_printable(part.body),
+        _ => part.body.as_bytes().to_vec(),
+    };
+    // UTF-8以外の文字コードには対応しない
+    Some(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// `Content-Type: multipart/mixed; boundary="..."`のようなヘッダーのパラメータ
+fn parameter<'a>(value: &'a str, name: &str) -> Option<&'a str> {
+    value
+        .split(';')
+        .skip(1)
+        .filter_map(|p| p.split_once('='))
+        .find(|(k, _)| k.trim().eq_ignore_ascii_case(name))
+        .map(|(_, v)| v.trim().trim_matches('"'))
+}
+
+fn decode_quoted_printable(s: &str) -> Vec<u8> {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] != b'=' {
+            decoded.push(bytes[i]);
+            i += 1;
+            continue;
+        }
+        // `=`で終わる行はソフト改行
+        let rest = &bytes[i + 1..];
+        if rest.starts_with(b"\r\n") {
+            i += 3;
+        } else if rest.starts_with(b"\n") {
+            i += 2;
+        } else if let Some(byte) = rest
+            .get(..2)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok())
+        {
+            decoded.push(byte);
+            i += 3;
+        } else {
+            decoded.push(b'=');
+            i += 1;
+        }
+    }
+    decoded
+}
+
+/// RFC 2047のencoded-word (`=?UTF-8?B?...?=`) をデコードする
+///
+/// UTF-8以外の文字コードのものはそのまま残す
+fn decode_words(value: &str) -> String {
+    let mut decoded = String::with_capacity(value.len());
+    let mut rest = value;
+    let mut last_encoded = false;
+    while let Some(start) = rest.find("=?") {
+        let (before, after) = rest.split_at(start);
+        let Some((word, remaining)) = decode_word(&after[2..]) else {
+            decoded += &rest[..start + 2];
+            rest = &rest[start + 2..];
+            last_encoded = false;
+            continue;
+        };
+        // 隣り合ったencoded-wordの間の空白は無視する
+        if !(last_encoded && before.trim().is_empty()) {
+            decoded += before;
+        }
+        decoded += &word;
+        rest = remaining;
+        last_encoded = true;
+    }
+    decoded += rest;
+    decoded
+}
+
+/// `charset?encoding?text?=`の部分をデコードして、残りと一緒に返す
+fn decode_word(s: &str) -> Option<(String, &str)> {
+    let (charset, s) = s.split_once('?')?;
+    let (encoding, s) = s.split_once('?')?;
+    let (text, rest) = s.split_once("?=")?;
+    // `UTF-8*ja`のように言語が付くこともある
+    let charset = charset.split('*').next().unwrap_or_default();
+    if !charset.eq_ignore_ascii_case("utf-8") && !charset.eq_ignore_ascii_case("us-ascii") {
+        return None;
+    }
+    let bytes = match encoding {
+        "B" | "b" => STANDARD.decode(text).ok()?,
+        "Q" | "q" => decode_quoted_printable(&text.replace('_', " ")),
+        _ => return None,
+    };
+    let word = String::from_utf8(bytes).ok()?;
+    Some((word, rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_encoded_words() {
+        assert_eq!(decode_words("=?UTF-8?B?5pel5pys6Kqe?="), "日本語");
+        assert_eq!(decode_words("=?utf-8?q?hello_world?="), "hello world");
+        assert_eq!(
+            decode_words("Re: =?UTF-8?Q?=E3=81=93=E3=82=93?= there"),
+            "Re: こん there"
+        );
+    }
+
+    #[test]
+    fn joins_adjacent_encoded_words() {
+        // 折り返されたSubjectは`Part::parse`で空白1つに連結される
+        assert_eq!(
+            decode_words("=?UTF-8?B?5pel5pys?= =?UTF-8?B?6Kqe?="),
+            "日本語"
+        );
+        assert_eq!(decode_words("=?UTF-8?Q?a?= b =?UTF-8?Q?c?="), "a b c");
+    }
+
+    #[test]
+    fn keeps_unsupported_or_broken_words() {
+        let sjis = "=?Shift_JIS?B?g2WDWINn?=";
+        assert_eq!(decode_words(sjis), sjis);
+        assert_eq!(decode_words("a =? b"), "a =? b");
+        assert_eq!(decode_words("=?UTF-8?X?abc?="), "=?UTF-8?X?abc?=");
+    }
+
+    #[test]
+    fn decodes_quoted_printable() {
+        let decoded = decode_quoted_printable("=E3=81=93=E3=82=93=\r\n=E3=81=AB=3D\n=ZZ");
+        assert_eq!(String::from_utf8(decoded).unwrap(), "こんに=\n=ZZ");
+    }
+
+    #[test]
+    fn handles_plain_mail() {
+        let mail = "From: =?UTF-8?B?5pel5pys6Kqe?= <a@example.com>\r\n\
+                    Subject: =?UTF-8?B?5Lu25ZCN44Gn44GZ?=\r\n\
+                    \r\n\
+                    body\r\n";
+        let message = handle(HeaderMap::new(), mail).unwrap();
+        assert_eq!(
+            message,
+            "### 件名です\nFrom: 日本語 <a@example.com>\nbody\n"
+        );
+    }
+
+    #[test]
+    fn unfolds_headers() {
+        let part = Part::parse("Subject: a\n\tb\nX-Empty:\n\nbody");
+        assert_eq!(part.header("subject"), Some("a b"));
+        assert_eq!(part.header("X-Empty"), Some(""));
+        assert_eq!(part.body, "body");
+    }
+
+    #[test]
+    fn picks_first_text_part_of_multipart() {
+        let mail = "Subject: test\n\
+                    Content-Type: multipart/alternative; boundary=\"b1\"\n\
+                    \n\
+                    preamble\n\
+                    --b1\n\
+                    Content-Type: text/html\n\
+                    \n\
+                    <p>html</p>\n\
+                    --b1\n\
+                    Content-Type: text/plain; charset=utf-8\n\
+                    Content-Transfer-Encoding: base64\n\
+                    \n\
+                    5pel5pys\n\
+                    6Kqe\n\
+                    --b1--\n";
+        let message = handle(HeaderMap::new(), mail).unwrap();
+        assert_eq!(message, "### test\n日本語\n");
+    }
+
+    #[test]
+    fn rejects_without_headers() {
+        let res = handle(HeaderMap::new(), "no headers here");
+        assert!(matches!(res, Err(Failure::Reject(_))));
+    }
+}
@@ -0,0 +1,102 @@
+use http::HeaderMap;
+use serde_json::Value;
+
+use domain::{Event, Failure, Webhook};
+
+use super::Handled;
+use super::utils::truncate;
+use crate::WebhookHandlerImpl;
+
+/// traQのメッセージの最大文字数
+const MAX_MESSAGE_LENGTH: usize = 10000;
+
+/// 整形したJSONを囲むコードブロックの記号
+const CODE_FENCE: (&str, &str) = ("```json\n", "\n```");
+
+impl WebhookHandlerImpl {
+    pub(crate) fn handle_generic(
+        &self,
+        webhook: &Webhook,
+        headers: HeaderMap,
+        payload: &str,
+    ) -> Result<Option<Handled>, Failure> {
+        let message = handle(webhook.template(), headers, payload)?;
+        let event_type = "generic".to_string();
+        let kind = event_type.clone().into();
+        let event = Event {
+            destination: webhook.destination(),
+            kind,
+            body: message.into(),
+            collapse: false,
+            deliveries: vec![],
+        };
+        Ok(Some(Handled { event, event_type }))
+    }
+}
+
+#[tracing::instrument(target = "wh_handler::generic::handle", skip_all)]
+fn handle(template: Option<&str>, _headers: HeaderMap, payload: &str) -> Result<String, Failure> {
+    let payload: Value = serde_json::from_str(payload).map_err(|e| {
+        let message = format!("Received invalid JSON: {e}");
+        Failure::reject_bad_request(message)
+    })?;
+    let message = match template {
+        Some(template) => truncate(render(template, &payload), MAX_MESSAGE_LENGTH),
+        None => {
+            // コードブロックが閉じるよう、中身だけを切り詰める
+            let (open, close) = CODE_FENCE;
+            let max_length = MAX_MESSAGE_LENGTH - open.len() - close.len();
+            // `Value`の`{:#}`は`to_string_pretty`と同じ出力で、失敗しない
+            let pretty = truncate(format!("{payload:#}"), max_length);
+            format!("{open}{pretty}{close}")
+        }
+    };
+    Ok(message)
+}
+
+/// `{{ $.path.to[0].field }}`を`payload`の該当する値で置き換える
+///
+/// 値が見つからない場合は空文字列になる
+fn render(template: &str, payload: &Value) -> String {
+    let mut result = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        result += &rest[..start];
+        let Some(len) = rest[start..].find("}}") else {
+            rest = &rest[start..];
+            break;
+        };
+        let path = rest[start + 2..start + len].trim();
+        match select(payload, path) {
+            Some(Value::String(s)) => result += s,
+            Some(Value::Null) | None => {}
+            Some(v) => result += &v.to_string(),
+        }
+        rest = &rest[start + len + 2..];
+    }
+    result += rest;
+    result
+}
+
+/// JSONPathのうち、`$`, `.key`, `[index]`, `["key"]`のみに対応する
+fn select<'a>(payload: &'a Value, path: &str) -> Option<&'a Value> {
+    let mut path = path.strip_prefix('$').unwrap_or(path);
+    let mut current = payload;
+    while !path.is_empty() {
+        if let Some(rest) = path.strip_prefix('[') {
+            let (key, rest) = rest.split_once(']')?;
+            let key = key.trim();
+            current = match key.parse::<usize>() {
+                Ok(index) => current.get(index)?,
+                Err(_) => current.get(key.trim_matches(['"', '\'']))?,
+            };
+            path = rest;
+        } else {
+            let rest = path.strip_prefix('.').unwrap_or(path);
+            let end = rest.find(['.', '[']).unwrap_or(rest.len());
+            current = current.get(&rest[..end])?;
+            path = &rest[end..];
+        }
+    }
+    Some(current)
+}
@@ -2,37 +2,45 @@ use std::str::from_utf8;
 
 use http::HeaderMap;
 use indoc::formatdoc;
+use itertools::Itertools;
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, utf8_percent_encode};
 use serde_json::Value;
 use teahook as th;
 
-use domain::{Event, EventSubscriber, Failure, Infra, Webhook};
+use domain::{Event, Failure, Webhook};
 
-use super::utils::{OptionExt, extract_header_value};
+use super::Handled;
+use super::utils::{OptionExt, extract_header_value, header_str, parse_payload};
 use crate::WebhookHandlerImpl;
 
+/// URLのパスの1セグメントに埋め込むときにエスケープする文字。RFC 3986の非予約文字以外
+const PATH_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
 impl WebhookHandlerImpl {
-    pub(crate) async fn handle_gitea<I>(
+    pub(crate) fn handle_gitea(
         &self,
-        infra: &I,
-        webhook: Webhook,
+        webhook: &Webhook,
         headers: HeaderMap,
         payload: &str,
-    ) -> Result<(), Failure>
-    where
-        I: Infra,
-    {
-        let subscriber = infra.event_subscriber();
+    ) -> Result<Option<Handled>, Failure> {
+        let event_type =
+            header_str(&headers, "X-Gitea-Event").unwrap_or_else(|| "gitea".to_string());
         let Some(message) = handle(headers, payload)? else {
-            return Ok(());
+            return Ok(None);
         };
-        let kind = "gitea".to_string().into(); // TODO: event_type
+        let kind = "gitea".to_string().into();
         let event = Event {
-            channel_id: webhook.channel_id,
+            destination: webhook.destination(),
             kind,
             body: message.into(),
+            collapse: false,
+            deliveries: vec![],
         };
-        subscriber.send(event).await?;
-        Ok(())
+        Ok(Some(Handled { event, event_type }))
     }
 }
 
@@ -44,11 +52,11 @@ fn handle(headers: HeaderMap, payload: &str) -> Result<Option<String>, Failure>
             match local_event_type {
                 $(stringify!($i) => {
                     tracing::info!("X-Gitea-Event: {local_event_type}");
-                    Some($i(from_str($p).map_err(anyhow::Error::from)?)?)
+                    Some($i(parse_payload($p)?)?)
                 })*
                 $(stringify!($di))|* => {
                     tracing::info!("X-Gitea-Event: {local_event_type}");
-                    default($t, from_str($p).map_err(anyhow::Error::from)?)
+                    default($t, parse_payload($p)?)
                 }
                 ut => {
                     let message = format!("unexpected event: `X-Gitea-Event: {ut}`");
@@ -58,7 +66,6 @@ fn handle(headers: HeaderMap, payload: &str) -> Result<Option<String>, Failure>
         };
     }
 
-    use serde_json::from_str;
     // https://github.com/traPtitech/gitea/blob/8abe54a9d4db1fdce7c517dc500a51e77d1f2c16/services/webhook/deliver.go#L124-L138
     // https://github.com/traPtitech/gitea/blob/8abe54a9d4db1fdce7c517dc500a51e77d1f2c16/modules/webhook/type.go#L11-L33
     let event_type = extract_header_value(&headers, "X-Gitea-Event").and_then(|v| {
@@ -69,14 +76,15 @@ fn handle(headers: HeaderMap, payload: &str) -> Result<Option<String>, Failure>
     })?;
     let message = match_event!(
         event_type => payload;
-        create, delete, fork, push, issues, pull_request;
-        default = [
-            issue_assign, issue_label, issue_milestone, issue_comment,
-            pull_request_assign, pull_request_label, pull_request_milestone,
-            pull_request_comment, pull_request_review_approved, pull_request_review_rejected,
-            pull_request_revew_comment, pull_request_sync, pull_request_review_request, wiki,
-            repository, release, package
-        ]
+        create, delete, fork, push, issues, pull_request,
+        issue_assign, issue_label, issue_milestone, issue_comment,
+        pull_request_assign, pull_request_label, pull_request_milestone,
+        pull_request_comment, pull_request_review_approved, pull_request_review_rejected,
+        pull_request_review_comment, pull_request_revew_comment,
+        pull_request_sync, pull_request_review_request,
+        wiki, repository, release, package;
+        // 比較的新しいGiteaが送ってくるもの
+        default = [status, workflow_run, workflow_job]
     );
     let Some(message) = message else {
         return Ok(None);
@@ -224,6 +232,363 @@ fn pull_request(payload: th::PullRequestPayload) -> Result<String, Failure> {
     Ok(message)
 }
 
+/// `X-Gitea-Event: issue_assign`
+fn issue_assign(payload: th::IssuePayload) -> Result<String, Failure> {
+    let th::IssuePayload {
+        action,
+        issue,
+        repository: repo,
+        sender,
+        ..
+    } = &payload;
+    unwrap_opt_boxed! {repo, sender, issue}
+    let action = action.to_string().replace('_', " ");
+    let message = formatdoc! {
+        r"
+            [{repo}] issue {issue} {action} by {sender}
+            assignees: {assignees}
+        ",
+        repo = repo_str(repo),
+        issue = issue_str(issue),
+        sender = user_str(sender),
+        assignees = users_str(issue.assignees.as_deref())
+    };
+    Ok(message)
+}
+
+/// `X-Gitea-Event: issue_label`
+fn issue_label(payload: th::IssuePayload) -> Result<String, Failure> {
+    let th::IssuePayload {
+        issue,
+        repository: repo,
+        sender,
+        ..
+    } = &payload;
+    unwrap_opt_boxed! {repo, sender, issue}
+    let message = formatdoc! {
+        r"
+            [{repo}] issue {issue} labels updated by {sender}
+            labels: {labels}
+        ",
+        repo = repo_str(repo),
+        issue = issue_str(issue),
+        sender = user_str(sender),
+        labels = labels_str(issue.labels.as_deref())
+    };
+    Ok(message)
+}
+
+/// `X-Gitea-Event: issue_milestone`
+fn issue_milestone(payload: th::IssuePayload) -> Result<String, Failure> {
+    let th::IssuePayload {
+        action,
+        issue,
+        repository: repo,
+        sender,
+        ..
+    } = &payload;
+    unwrap_opt_boxed! {repo, sender, issue}
+    let message = format!(
+        "[{repo}] issue {issue} {action} {milestone} by {sender}\n",
+        repo = repo_str(repo),
+        issue = issue_str(issue),
+        milestone = milestone_str(issue.milestone.as_deref()),
+        sender = user_str(sender)
+    );
+    Ok(message)
+}
+
+/// `X-Gitea-Event: issue_comment`
+fn issue_comment(payload: th::IssueCommentPayload) -> Result<String, Failure> {
+    let th::IssueCommentPayload {
+        action,
+        issue,
+        comment,
+        repository: repo,
+        sender,
+        ..
+    } = &payload;
+    unwrap_opt_boxed! {repo, sender, issue, comment}
+    let message = formatdoc! {
+        r"
+            [{repo}] issue {issue}: comment [{action}]({comment_url}) by {sender}
+            {body}
+        ",
+        repo = repo_str(repo),
+        issue = issue_str(issue),
+        comment_url = &comment.html_url,
+        sender = user_str(sender),
+        body = body_str(&comment.body, "...")
+    };
+    Ok(message)
+}
+
+/// `X-Gitea-Event: pull_request_assign`
+fn pull_request_assign(payload: th::PullRequestPayload) -> Result<String, Failure> {
+    let th::PullRequestPayload {
+        action,
+        pull_request,
+        repository: repo,
+        sender,
+        ..
+    } = &payload;
+    unwrap_opt_boxed! {repo, sender, pull_request}
+    let action = action.to_string().replace('_', " ");
+    let message = formatdoc! {
+        r"
+            [{repo}] Pull Request {pr} {action} by {sender}
+            assignees: {assignees}
+        ",
+        repo = repo_str(repo),
+        pr = pr_str(pull_request),
+        sender = user_str(sender),
+        assignees = users_str(pull_request.assignees.as_deref())
+    };
+    Ok(message)
+}
+
+/// `X-Gitea-Event: pull_request_label`
+fn pull_request_label(payload: th::PullRequestPayload) -> Result<String, Failure> {
+    let th::PullRequestPayload {
+        pull_request,
+        repository: repo,
+        sender,
+        ..
+    } = &payload;
+    unwrap_opt_boxed! {repo, sender, pull_request}
+    let message = formatdoc! {
+        r"
+            [{repo}] Pull Request {pr} labels updated by {sender}
+            labels: {labels}
+        ",
+        repo = repo_str(repo),
+        pr = pr_str(pull_request),
+        sender = user_str(sender),
+        labels = labels_str(pull_request.labels.as_deref())
+    };
+    Ok(message)
+}
+
+/// `X-Gitea-Event: pull_request_milestone`
+fn pull_request_milestone(payload: th::PullRequestPayload) -> Result<String, Failure> {
+    let th::PullRequestPayload {
+        action,
+        pull_request,
+        repository: repo,
+        sender,
+        ..
+    } = &payload;
+    unwrap_opt_boxed! {repo, sender, pull_request}
+    let message = format!(
+        "[{repo}] Pull Request {pr} {action} {milestone} by {sender}\n",
+        repo = repo_str(repo),
+        pr = pr_str(pull_request),
+        milestone = milestone_str(pull_request.milestone.as_deref()),
+        sender = user_str(sender)
+    );
+    Ok(message)
+}
+
+/// `X-Gitea-Event: pull_request_comment`
+fn pull_request_comment(payload: th::IssueCommentPayload) -> Result<String, Failure> {
+    let th::IssueCommentPayload {
+        action,
+        issue,
+        comment,
+        repository: repo,
+        sender,
+        ..
+    } = &payload;
+    unwrap_opt_boxed! {repo, sender, issue, comment}
+    let message = formatdoc! {
+        r"
+            [{repo}] Pull Request {pr}: comment [{action}]({comment_url}) by {sender}
+            {body}
+        ",
+        repo = repo_str(repo),
+        pr = issue_str(issue),
+        comment_url = &comment.html_url,
+        sender = user_str(sender),
+        body = body_str(&comment.body, "...")
+    };
+    Ok(message)
+}
+
+/// `X-Gitea-Event: pull_request_review_approved`
+fn pull_request_review_approved(payload: th::PullRequestPayload) -> Result<String, Failure> {
+    pull_request_review(&payload, ":white_check_mark: approved")
+}
+
+/// `X-Gitea-Event: pull_request_review_rejected`
+fn pull_request_review_rejected(payload: th::PullRequestPayload) -> Result<String, Failure> {
+    pull_request_review(&payload, ":x: changes requested")
+}
+
+/// `X-Gitea-Event: pull_request_review_comment`
+fn pull_request_review_comment(payload: th::PullRequestPayload) -> Result<String, Failure> {
+    pull_request_review(&payload, ":speech_balloon: commented")
+}
+
+/// `X-Gitea-Event: pull_request_revew_comment`
+///
+/// 古いGiteaはこの綴りで送ってくる
+fn pull_request_revew_comment(payload: th::PullRequestPayload) -> Result<String, Failure> {
+    pull_request_review_comment(payload)
+}
+
+fn pull_request_review(payload: &th::PullRequestPayload, state: &str) -> Result<String, Failure> {
+    let th::PullRequestPayload {
+        pull_request,
+        review,
+        repository: repo,
+        sender,
+        ..
+    } = payload;
+    unwrap_opt_boxed! {repo, sender, pull_request}
+    let content = review.as_deref().map_or("", |r| r.content.as_str());
+    let message = formatdoc! {
+        r"
+            [{repo}] Pull Request review {pr} {state} by {sender}
+            {body}
+        ",
+        repo = repo_str(repo),
+        pr = pr_str(pull_request),
+        sender = user_str(sender),
+        body = body_str(content, &pull_request.html_url)
+    };
+    Ok(message)
+}
+
+/// `X-Gitea-Event: pull_request_sync`
+fn pull_request_sync(payload: th::PullRequestPayload) -> Result<String, Failure> {
+    let th::PullRequestPayload {
+        pull_request,
+        repository: repo,
+        sender,
+        ..
+    } = &payload;
+    unwrap_opt_boxed! {repo, sender, pull_request}
+    let message = format!(
+        "[{repo}] Pull Request {pr} synchronized by {sender}\n",
+        repo = repo_str(repo),
+        pr = pr_str(pull_request),
+        sender = user_str(sender)
+    );
+    Ok(message)
+}
+
+/// `X-Gitea-Event: pull_request_review_request`
+fn pull_request_review_request(payload: th::PullRequestPayload) -> Result<String, Failure> {
+    let th::PullRequestPayload {
+        action,
+        pull_request,
+        requested_reviewer,
+        repository: repo,
+        sender,
+        ..
+    } = &payload;
+    unwrap_opt_boxed! {repo, sender, pull_request}
+    let action = action.to_string().replace('_', " ");
+    let reviewer = requested_reviewer
+        .as_deref()
+        .map_or_else(|| "someone".to_string(), user_str);
+    let message = format!(
+        "[{repo}] Pull Request {pr} {action} for {reviewer} by {sender}\n",
+        repo = repo_str(repo),
+        pr = pr_str(pull_request),
+        sender = user_str(sender)
+    );
+    Ok(message)
+}
+
+/// `X-Gitea-Event: wiki`
+fn wiki(payload: th::WikiPayload) -> Result<String, Failure> {
+    let th::WikiPayload {
+        action,
+        page,
+        comment,
+        repository: repo,
+        sender,
+        ..
+    } = &payload;
+    unwrap_opt_boxed! {repo, sender}
+    let message = formatdoc! {
+        r"
+            [{repo}] wiki page [{page}]({html_url}/wiki/{page_path}) {action} by {sender}
+            {comment}
+        ",
+        html_url = &repo.html_url,
+        page_path = utf8_percent_encode(page, PATH_SEGMENT),
+        repo = repo_str(repo),
+        sender = user_str(sender)
+    };
+    Ok(message)
+}
+
+/// `X-Gitea-Event: repository`
+fn repository(payload: th::RepositoryPayload) -> Result<String, Failure> {
+    let th::RepositoryPayload {
+        action,
+        repository: repo,
+        sender,
+        ..
+    } = &payload;
+    unwrap_opt_boxed! {repo, sender}
+    let message = format!(
+        "Repository {repo} {action} by {sender}\n",
+        repo = repo_str(repo),
+        sender = user_str(sender)
+    );
+    Ok(message)
+}
+
+/// `X-Gitea-Event: release`
+fn release(payload: th::ReleasePayload) -> Result<String, Failure> {
+    let th::ReleasePayload {
+        action,
+        release,
+        repository: repo,
+        sender,
+        ..
+    } = &payload;
+    unwrap_opt_boxed! {repo, sender, release}
+    let th::Release {
+        name,
+        tag_name,
+        html_url,
+        ..
+    } = release;
+    let message = format!(
+        "[{repo}] Release [{name}]({html_url}) (`{tag_name}`) {action} by {sender}\n",
+        repo = repo_str(repo),
+        sender = user_str(sender)
+    );
+    Ok(message)
+}
+
+/// `X-Gitea-Event: package`
+fn package(payload: th::PackagePayload) -> Result<String, Failure> {
+    let th::PackagePayload {
+        action,
+        package,
+        sender,
+        ..
+    } = &payload;
+    unwrap_opt_boxed! {sender, package}
+    let th::Package {
+        r#type: kind,
+        name,
+        version,
+        html_url,
+        ..
+    } = package;
+    let message = format!(
+        "[{kind}] Package [{name}:{version}]({html_url}) {action} by {sender}\n",
+        sender = user_str(sender)
+    );
+    Ok(message)
+}
+
 /// `X-Gitea-Event: *`
 fn default(_event_type: &str, _payload: Value) -> Option<String> {
     None
@@ -256,3 +621,53 @@ fn pr_str(pr: &th::PullRequest) -> String {
     } = pr;
     format!("[#{id} {title}]({html_url})")
 }
+
+fn issue_str(issue: &th::Issue) -> String {
+    let th::Issue {
+        number,
+        title,
+        html_url,
+        ..
+    } = issue;
+    format!("[#{number} {title}]({html_url})")
+}
+
+fn milestone_str(milestone: Option<&th::Milestone>) -> String {
+    milestone.map_or_else(
+        || "milestone".to_string(),
+        |m| format!("milestone `{title}`", title = m.title),
+    )
+}
+
+fn users_str(users: Option<&[Option<Box<th::User>>]>) -> String {
+    let users = users
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|u| user_str(u))
+        .join(", ");
+    if users.is_empty() {
+        "none".to_string()
+    } else {
+        users
+    }
+}
+
+fn labels_str(labels: Option<&[Option<Box<th::Label>>]>) -> String {
+    let labels = labels
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|l| format!("`{name}`", name = l.name))
+        .join(", ");
+    if labels.is_empty() {
+        "none".to_string()
+    } else {
+        labels
+    }
+}
+
+/// 5行を超える本文は`alt`で置き換える
+fn body_str<'a>(body: &'a str, alt: &'a str) -> &'a str {
+    if body.lines().count() > 5 { alt } else { body }
+}
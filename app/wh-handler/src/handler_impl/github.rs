@@ -7,34 +7,33 @@ use itertools::Itertools;
 use paste::paste;
 use serde_json::Value;
 
-use domain::{Event, EventSubscriber, Failure, Infra, Webhook};
+use domain::{Event, Failure, Webhook};
 
-use super::utils::extract_header_value;
+use super::Handled;
+use super::utils::{extract_header_value, header_str, parse_payload};
 use crate::WebhookHandlerImpl;
 
 impl WebhookHandlerImpl {
-    pub(crate) async fn handle_github<I>(
+    pub(crate) fn handle_github(
         &self,
-        infra: &I,
-        webhook: Webhook,
+        webhook: &Webhook,
         headers: HeaderMap,
         payload: &str,
-    ) -> Result<(), Failure>
-    where
-        I: Infra,
-    {
-        let subscriber = infra.event_subscriber();
+    ) -> Result<Option<Handled>, Failure> {
+        let event_type =
+            header_str(&headers, "X-GitHub-Event").unwrap_or_else(|| "github".to_string());
         let Some(message) = handle(headers, payload)? else {
-            return Ok(());
+            return Ok(None);
         };
-        let kind = "github".to_string().into(); // TODO: event_type
+        let kind = "github".to_string().into();
         let event = Event {
-            channel_id: webhook.channel_id,
+            destination: webhook.destination(),
             kind,
             body: message.into(),
+            collapse: false,
+            deliveries: vec![],
         };
-        subscriber.send(event).await?;
-        Ok(())
+        Ok(Some(Handled { event, event_type }))
     }
 }
 
@@ -43,13 +42,12 @@ fn handle(headers: HeaderMap, payload: &str) -> Result<Option<String>, Failure>
     macro_rules! match_event {
         ($t:expr => $p:expr; $($i:ident),*) => {
             match $t {
-                $(stringify!($i) => $i(from_str($p).map_err(anyhow::Error::from)?),)*
-                _ => default($t, from_str($p).map_err(anyhow::Error::from)?),
+                $(stringify!($i) => $i(parse_payload($p)?),)*
+                _ => default($t, parse_payload($p)?),
             }
         };
     }
 
-    use serde_json::from_str;
     let event_type = extract_header_value(&headers, "X-GitHub-Event").and_then(|v| {
         from_utf8(v).map_err(|e| {
             let message = format!("Received invalid X-GitHub-Event: {e}");
@@ -66,7 +64,10 @@ fn handle(headers: HeaderMap, payload: &str) -> Result<Option<String>, Failure>
         pull_request_review, pull_request_review_thread,
         repository,
         star, watch,
-        workflow_run, workflow_job
+        workflow_run, workflow_job,
+        label, milestone, member, team_add, public, gollum,
+        commit_comment, status,
+        projects_v2, projects_v2_item, merge_group, sponsorship
     );
     Ok(message)
 }
@@ -714,6 +715,397 @@ fn workflow_run(payload: gh::WorkflowRunEvent) -> Option<String> {
     Some(message)
 }
 
+/// `X-GitHub-Event: label`
+fn label(payload: gh::LabelEvent) -> Option<String> {
+    macro_rules! label_event {
+        ($i:ident, $kind:ident) => {{
+            paste! {
+                let gh::[< Label $kind:camel Event >] {
+                    repository, sender, label, ..
+                } = $i;
+                (stringify!([< $kind:snake:lower >]), repository, sender, label)
+            }
+        }};
+    }
+
+    use gh::LabelEvent::{Created, Deleted, Edited};
+
+    let (action, repository, sender, label) = match &payload {
+        Created(l) => label_event!(l, created),
+        Deleted(l) => label_event!(l, deleted),
+        Edited(l) => label_event!(l, edited),
+    };
+    let repo = repo_str(repository);
+    let label = label_str(label);
+    let sender = user_str(sender);
+    let message = format!("[{repo}] Label {label} {action} by {sender}\n");
+    Some(message)
+}
+
+/// `X-GitHub-Event: milestone`
+fn milestone(payload: gh::MilestoneEvent) -> Option<String> {
+    macro_rules! milestone_event {
+        ($i:ident, $kind:ident) => {{
+            paste! {
+                let gh::[< Milestone $kind:camel Event >] {
+                    repository, sender, milestone, ..
+                } = $i;
+                (stringify!([< $kind:snake:lower >]), repository, sender, milestone)
+            }
+        }};
+    }
+
+    macro_rules! milestone_event_nested {
+        ($i:ident, $kind:ident) => {{
+            paste! {
+                let gh::[< Milestone $kind:camel Event >] {
+                    repository, sender, milestone, ..
+                } = $i;
+                (stringify!([< $kind:snake:lower >]), repository, sender, &milestone.milestone)
+            }
+        }};
+    }
+
+    use gh::MilestoneEvent as Me;
+
+    let (action, repository, sender, milestone) = match &payload {
+        Me::Closed(m) => milestone_event_nested!(m, closed),
+        Me::Created(m) => milestone_event_nested!(m, created),
+        Me::Deleted(m) => milestone_event!(m, deleted),
+        Me::Edited(m) => milestone_event!(m, edited),
+        Me::Opened(m) => milestone_event_nested!(m, opened),
+    };
+    let message_headline = format!(
+        "[{repo}] Milestone {milestone} {action} by {sender}",
+        repo = repo_str(repository),
+        milestone = milestone_str(milestone),
+        sender = user_str(sender)
+    );
+    // 期日の変更は見落とされやすいので明示する
+    let due_on = milestone.due_on.as_deref().unwrap_or("none");
+    let message_body = match &payload {
+        Me::Created(_) => format!("due: {due_on}"),
+        Me::Edited(e) => match e.changes.due_on.as_ref() {
+            Some(change) => format!("due: {from} -> {due_on}", from = change.from),
+            None => String::new(),
+        },
+        _ => String::new(),
+    };
+    let message = format!("{message_headline}\n{message_body}");
+    Some(message)
+}
+
+/// `X-GitHub-Event: member`
+fn member(payload: gh::MemberEvent) -> Option<String> {
+    macro_rules! member_event {
+        ($i:ident, $kind:ident) => {{
+            paste! {
+                let gh::[< Member $kind:camel Event >] {
+                    repository, sender, member, ..
+                } = $i;
+                (stringify!([< $kind:snake:lower >]), repository, sender, member)
+            }
+        }};
+    }
+
+    use gh::MemberEvent::{Added, Edited, Removed};
+
+    let (action, repository, sender, member) = match &payload {
+        Added(m) => member_event!(m, added),
+        Edited(m) => member_event!(m, edited),
+        Removed(m) => member_event!(m, removed),
+    };
+    let repo = repo_str(repository);
+    let member = user_str(member);
+    let sender = user_str(sender);
+    let message = format!("[{repo}] Collaborator {member} {action} by {sender}\n");
+    Some(message)
+}
+
+/// `X-GitHub-Event: team_add`
+fn team_add(payload: gh::TeamAddEvent) -> Option<String> {
+    let gh::TeamAddEvent {
+        team,
+        repository,
+        sender,
+        ..
+    } = &payload;
+    let repo = repo_str(repository);
+    let team = team_str(team);
+    let sender = user_str(sender);
+    let message = format!("[{repo}] Team {team} added by {sender}\n");
+    Some(message)
+}
+
+/// `X-GitHub-Event: public`
+fn public(payload: gh::PublicEvent) -> Option<String> {
+    let gh::PublicEvent {
+        repository, sender, ..
+    } = &payload;
+    let repo = repo_str(repository);
+    let sender = user_str(sender);
+    let message = format!("[{repo}] :tada: Repository made public by {sender} :tada:\n");
+    Some(message)
+}
+
+/// `X-GitHub-Event: gollum`
+fn gollum(payload: gh::GollumEvent) -> Option<String> {
+    use gh::GollumEventPagesAction as Action;
+
+    let gh::GollumEvent {
+        pages,
+        repository,
+        sender,
+        ..
+    } = &payload;
+    let repo = repo_str(repository);
+    let sender = user_str(sender);
+    let page_count = pages.len();
+    let page_unit = if page_count == 1 { "" } else { "s" };
+    let pages = pages
+        .iter()
+        .map(|p| {
+            let action = match p.action {
+                Action::Created => "created",
+                Action::Edited => "edited",
+            };
+            format!(
+                "- [{title}]({html_url}) {action}",
+                title = p.title,
+                html_url = p.html_url
+            )
+        })
+        .join("\n");
+    let message = formatdoc! {
+        r"
+            [{repo}] {page_count} wiki page{page_unit} updated by {sender}
+            {pages}
+        "
+    };
+    Some(message)
+}
+
+/// `X-GitHub-Event: commit_comment`
+fn commit_comment(payload: gh::CommitCommentEvent) -> Option<String> {
+    let gh::CommitCommentEvent {
+        comment,
+        repository,
+        sender,
+        ..
+    } = &payload;
+    let repo = repo_str(repository);
+    let commit_id = &comment.commit_id[0..7];
+    let comment_url = &comment.html_url;
+    let sender = user_str(sender);
+    let message_body_lines = comment.body.lines().collect::<Vec<_>>();
+    let message_body = if message_body_lines.len() > 5 {
+        "..."
+    } else {
+        &comment.body
+    };
+    let message = formatdoc! {
+        r"
+            [{repo}] Commit `{commit_id}`: comment [created]({comment_url}) by {sender}
+            {message_body}
+        "
+    };
+    Some(message)
+}
+
+/// `X-GitHub-Event: status`
+fn status(payload: gh::StatusEvent) -> Option<String> {
+    use gh::StatusEventState as State;
+
+    let gh::StatusEvent {
+        sha,
+        state,
+        context,
+        description,
+        target_url,
+        repository,
+        ..
+    } = &payload;
+    let repo = repo_str(repository);
+    let sha = &sha[0..7];
+    let state = match state {
+        State::Error => ":x: error",
+        State::Failure => ":x: failed",
+        State::Pending => ":construction: pending",
+        State::Success => ":white_check_mark: success",
+    };
+    let description = description.as_deref().unwrap_or_default();
+    let context = match target_url.as_deref() {
+        Some(url) => format!("[{context}]({url})"),
+        None => format!("`{context}`"),
+    };
+    let message = format!("[{repo}] Commit `{sha}` status {context} {state} {description}\n");
+    Some(message)
+}
+
+/// `X-GitHub-Event: projects_v2`
+fn projects_v2(payload: gh::ProjectsV2Event) -> Option<String> {
+    macro_rules! projects_v2_event {
+        ($i:ident, $kind:ident) => {{
+            paste! {
+                let gh::[< ProjectsV2 $kind:camel Event >] {
+                    organization, sender, projects_v2, ..
+                } = $i;
+                (stringify!([< $kind:snake:lower >]), organization, sender, projects_v2)
+            }
+        }};
+    }
+
+    use gh::ProjectsV2Event as Pe;
+
+    let (action, organization, sender, project) = match &payload {
+        Pe::Closed(p) => projects_v2_event!(p, closed),
+        Pe::Created(p) => projects_v2_event!(p, created),
+        Pe::Deleted(p) => projects_v2_event!(p, deleted),
+        Pe::Edited(p) => projects_v2_event!(p, edited),
+        Pe::Reopened(p) => projects_v2_event!(p, reopened),
+    };
+    let org = &organization.login;
+    let project = format!(
+        "[#{number} {title}](https://github.com/orgs/{org}/projects/{number})",
+        number = project.number,
+        title = project.title,
+    );
+    let sender = user_str(sender);
+    let message = format!("[{org}] Project {project} {action} by {sender}\n");
+    Some(message)
+}
+
+/// `X-GitHub-Event: projects_v2_item`
+///
+/// `changes`の形がactionごとに大きく異なるので`Value`のまま扱う
+fn projects_v2_item(payload: Value) -> Option<String> {
+    let Value::Object(payload) = payload else {
+        return None;
+    };
+    let action = payload.get("action")?.as_str()?;
+    let org = payload.get("organization")?.get("login")?.as_str()?;
+    let item = payload.get("projects_v2_item")?;
+    let content_type = match item.get("content_type")?.as_str()? {
+        "DraftIssue" => "draft issue",
+        "PullRequest" => "pull request",
+        _ => "issue",
+    };
+    let sender = value_user_str(payload.get("sender")?)?;
+    let changes = payload.get("changes");
+    let message = match action {
+        "edited" => {
+            let Some(field_value) = changes.and_then(|c| c.get("field_value")) else {
+                return Some(format!(
+                    "[{org}] Project item ({content_type}) edited by {sender}\n"
+                ));
+            };
+            let field = field_value
+                .get("field_name")
+                .or_else(|| field_value.get("field_type"))
+                .and_then(Value::as_str)
+                .unwrap_or("unknown");
+            let value_str = |v: Option<&Value>| {
+                let v = v?;
+                v.as_str()
+                    .or_else(|| v.get("name").and_then(Value::as_str))
+                    .or_else(|| v.get("title").and_then(Value::as_str))
+                    .map(ToString::to_string)
+                    .or_else(|| v.as_f64().map(|f| f.to_string()))
+            };
+            let from = value_str(field_value.get("from")).unwrap_or_else(|| "none".to_string());
+            let to = value_str(field_value.get("to")).unwrap_or_else(|| "none".to_string());
+            formatdoc! {
+                r"
+                    [{org}] Project item ({content_type}) field `{field}` changed by {sender}
+                    {from} -> {to}
+                "
+            }
+        }
+        "converted" => {
+            format!("[{org}] Project item converted from draft issue to issue by {sender}\n")
+        }
+        action => format!("[{org}] Project item ({content_type}) {action} by {sender}\n"),
+    };
+    Some(message)
+}
+
+/// `X-GitHub-Event: merge_group`
+fn merge_group(payload: gh::MergeGroupEvent) -> Option<String> {
+    use gh::MergeGroupEvent::{ChecksRequested, Destroyed};
+
+    let message = match &payload {
+        ChecksRequested(m) => {
+            let gh::MergeGroupChecksRequestedEvent {
+                repository,
+                merge_group,
+                ..
+            } = m;
+            let repo = repo_str(repository.as_ref()?);
+            let base_ref = merge_group.base_ref.trim_start_matches("refs/heads/");
+            let sha = &merge_group.head_sha[0..7];
+            let commit = merge_group
+                .head_commit
+                .message
+                .lines()
+                .next()
+                .unwrap_or_default();
+            format!("[{repo}:{base_ref}] Merge queue checks requested for `{sha}` {commit}\n")
+        }
+        Destroyed(m) => {
+            use gh::MergeGroupDestroyedEventReason as Reason;
+            let gh::MergeGroupDestroyedEvent {
+                repository,
+                merge_group,
+                reason,
+                ..
+            } = m;
+            let repo = repo_str(repository.as_ref()?);
+            let base_ref = merge_group.base_ref.trim_start_matches("refs/heads/");
+            let sha = &merge_group.head_sha[0..7];
+            let reason = match reason {
+                Reason::Dequeued => "dequeued",
+                Reason::Invalidated => "invalidated",
+                Reason::Merged => "merged",
+            };
+            format!("[{repo}:{base_ref}] Merge group `{sha}` destroyed ({reason})\n")
+        }
+    };
+    Some(message)
+}
+
+/// `X-GitHub-Event: sponsorship`
+fn sponsorship(payload: gh::SponsorshipEvent) -> Option<String> {
+    macro_rules! sponsorship_event {
+        ($i:ident, $kind:ident) => {{
+            paste! {
+                let gh::[< Sponsorship $kind:camel Event >] {
+                    sender, sponsorship, ..
+                } = $i;
+                let sponsorship = (&sponsorship.sponsorable, &sponsorship.tier.name);
+                (stringify!([< $kind:snake:lower >]), sender, sponsorship)
+            }
+        }};
+    }
+
+    use gh::SponsorshipEvent as Se;
+
+    let (action, sender, sponsorship) = match &payload {
+        Se::Cancelled(s) => sponsorship_event!(s, cancelled),
+        Se::Created(s) => sponsorship_event!(s, created),
+        Se::Edited(s) => sponsorship_event!(s, edited),
+        Se::PendingCancellation(s) => sponsorship_event!(s, pending_cancellation),
+        Se::PendingTierChange(s) => sponsorship_event!(s, pending_tier_change),
+        Se::TierChanged(s) => sponsorship_event!(s, tier_changed),
+    };
+    let (sponsorable, tier) = sponsorship;
+    let sponsorable = user_str(sponsorable);
+    let action = action.replace('_', " ");
+    let sender = user_str(sender);
+    let message =
+        format!("[{sponsorable}] :heart: Sponsorship `{tier}` {action} by {sender} :heart:\n");
+    Some(message)
+}
+
 /// `X-GitHub-Event: *`
 fn default(_event_type: &str, _payload: Value) -> Option<String> {
     None
@@ -727,6 +1119,13 @@ fn user_str(user: &gh::User) -> String {
     format!("[{login}]({html_url})")
 }
 
+/// `{"login": .., "html_url": ..}` -> `[login](html_url)`
+fn value_user_str(user: &Value) -> Option<String> {
+    let login = user.get("login")?.as_str()?;
+    let html_url = user.get("html_url")?.as_str()?;
+    Some(format!("[{login}]({html_url})"))
+}
+
 /// `repository` -> `[repository.full_name](repository.html_url)`
 fn repo_str(repo: &gh::Repository) -> String {
     let gh::Repository {
@@ -813,6 +1212,26 @@ fn workflow_job_str(workflow_job: &gh::WorkflowJob) -> String {
     }
 }
 
+/// `label` -> `` `label.name` ``
+fn label_str(label: &gh::Label) -> String {
+    let gh::Label { name, .. } = label;
+    format!("`{name}`")
+}
+
+/// `milestone` -> `[milestone.title](milestone.html_url)`
+fn milestone_str(milestone: &gh::Milestone) -> String {
+    let gh::Milestone {
+        title, html_url, ..
+    } = milestone;
+    format!("[{title}]({html_url})")
+}
+
+/// `team` -> `[team.name](team.html_url)`
+fn team_str(team: &gh::Team) -> String {
+    let gh::Team { name, html_url, .. } = team;
+    format!("[{name}]({html_url})")
+}
+
 fn workflow_steps_str<'a, I>(steps: I) -> String
 where
     I: IntoIterator<Item = &'a gh::WorkflowStep<'a>>,
@@ -0,0 +1,207 @@
+use std::collections::BTreeMap;
+
+use http::HeaderMap;
+use indoc::formatdoc;
+use itertools::Itertools;
+use serde::Deserialize;
+use serde_json::Value;
+
+use domain::{Event, Failure, Webhook};
+
+use super::Handled;
+use super::utils::{parse_payload, verify_authorization};
+use crate::WebhookHandlerImpl;
+
+impl WebhookHandlerImpl {
+    pub(crate) fn handle_grafana(
+        &self,
+        webhook: &Webhook,
+        headers: HeaderMap,
+        payload: &str,
+    ) -> Result<Option<Handled>, Failure> {
+        let Some((group_key, event_type, message)) = handle(webhook.secret(), headers, payload)?
+        else {
+            return Ok(None);
+        };
+        // 同じグループの通知はnotifierでまとめられる
+        let kind = format!("grafana:{group_key}").into();
+        let event = Event {
+            destination: webhook.destination(),
+            kind,
+            body: message.into(),
+            collapse: false,
+            deliveries: vec![],
+        };
+        Ok(Some(Handled { event, event_type }))
+    }
+}
+
+/// <https://grafana.com/docs/grafana/latest/alerting/configure-notifications/manage-contact-points/integrations/webhook-notifier/>
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Payload {
+    group_key: String,
+    status: Status,
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    truncated_alerts: usize,
+    alerts: Vec<Alert>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Status {
+    Firing,
+    Resolved,
+}
+
+impl Status {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Firing => "firing",
+            Self::Resolved => "resolved",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Alert {
+    status: Status,
+    #[serde(default)]
+    labels: BTreeMap<String, String>,
+    #[serde(default)]
+    annotations: BTreeMap<String, String>,
+    #[serde(default)]
+    values: Option<BTreeMap<String, Value>>,
+    starts_at: String,
+    ends_at: String,
+    #[serde(default, rename = "generatorURL")]
+    generator_url: String,
+    #[serde(default, rename = "silenceURL")]
+    silence_url: String,
+    #[serde(default, rename = "dashboardURL")]
+    dashboard_url: String,
+    #[serde(default, rename = "panelURL")]
+    panel_url: String,
+}
+
+/// グループのキー・イベントの種類 (`firing`, `resolved`)・メッセージを返す
+#[tracing::instrument(target = "wh_handler::grafana::handle", skip_all)]
+fn handle(
+    secret: Option<&str>,
+    headers: HeaderMap,
+    payload: &str,
+) -> Result<Option<(String, String, String)>, Failure> {
+    // Contact pointの "Authorization Header - Credentials" に設定された値
+    if let Some(secret) = secret {
+        verify_authorization(&headers, secret)?;
+    }
+    let payload: Payload = parse_payload(payload)?;
+    tracing::info!(
+        group_key = payload.group_key,
+        "grafana: {:?}",
+        payload.status
+    );
+    if payload.alerts.is_empty() {
+        return Ok(None);
+    }
+    let message = group_str(&payload);
+    Ok(Some((
+        payload.group_key,
+        payload.status.as_str().to_string(),
+        message,
+    )))
+}
+
+fn group_str(payload: &Payload) -> String {
+    let Payload {
+        status,
+        title,
+        truncated_alerts,
+        alerts,
+        ..
+    } = payload;
+    let headline_icon = match status {
+        Status::Firing => ":fire:",
+        Status::Resolved => ":white_check_mark:",
+    };
+    let (firing, resolved): (Vec<_>, Vec<_>) =
+        alerts.iter().partition(|a| a.status == Status::Firing);
+    let sections = [
+        (":fire: Firing", &firing),
+        (":white_check_mark: Resolved", &resolved),
+    ]
+    .into_iter()
+    .filter(|(_, alerts)| !alerts.is_empty())
+    .map(|(title, alerts)| {
+        let body = alerts.iter().map(|a| alert_str(a)).join("\n");
+        format!("#### {title}\n{body}")
+    })
+    .join("\n");
+    let truncated = if *truncated_alerts > 0 {
+        format!("\n({truncated_alerts} alerts truncated)")
+    } else {
+        String::new()
+    };
+    format!("[Grafana] {headline_icon} {title}\n{sections}{truncated}\n")
+}
+
+fn alert_str(alert: &Alert) -> String {
+    let Alert {
+        status,
+        labels,
+        annotations,
+        values,
+        starts_at,
+        ends_at,
+        ..
+    } = alert;
+    let name = labels.get("alertname").map_or("alert", String::as_str);
+    let summary = annotations.get("summary").map_or("", String::as_str);
+    let period = match status {
+        Status::Firing => format!("since {starts_at}"),
+        Status::Resolved => format!("{starts_at} - {ends_at}"),
+    };
+    let values = values
+        .iter()
+        .flatten()
+        .map(|(k, v)| format!("`{k}={v}`"))
+        .join(" ");
+    let description = annotations
+        .get("description")
+        .map(|d| format!("\n    {}", d.lines().join("\n    ")))
+        .unwrap_or_default();
+    formatdoc! {
+        r"
+            - **{name}** {summary}
+                {values} {period}
+                {links}{description}
+        ",
+        links = links_str(alert),
+    }
+    .trim_end()
+    .to_string()
+}
+
+/// 空文字列のURLは省く
+fn links_str(alert: &Alert) -> String {
+    let Alert {
+        generator_url,
+        silence_url,
+        dashboard_url,
+        panel_url,
+        ..
+    } = alert;
+    [
+        ("dashboard", dashboard_url),
+        ("panel", panel_url),
+        ("silence", silence_url),
+        ("source", generator_url),
+    ]
+    .into_iter()
+    .filter(|(_, url)| !url.is_empty())
+    .map(|(text, url)| format!("[{text}]({url})"))
+    .join(" ")
+}
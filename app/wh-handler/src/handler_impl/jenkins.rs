@@ -0,0 +1,119 @@
+use http::HeaderMap;
+use serde::Deserialize;
+
+use domain::{Event, Failure, Webhook};
+
+use super::Handled;
+use super::utils::{duration_str, parse_payload};
+use crate::WebhookHandlerImpl;
+
+impl WebhookHandlerImpl {
+    pub(crate) fn handle_jenkins(
+        &self,
+        webhook: &Webhook,
+        headers: HeaderMap,
+        payload: &str,
+    ) -> Result<Option<Handled>, Failure> {
+        let Some((event_type, message)) = handle(headers, payload)? else {
+            return Ok(None);
+        };
+        let kind = "jenkins".to_string().into();
+        let event = Event {
+            destination: webhook.destination(),
+            kind,
+            body: message.into(),
+            collapse: false,
+            deliveries: vec![],
+        };
+        Ok(Some(Handled { event, event_type }))
+    }
+}
+
+/// <https://plugins.jenkins.io/notification/>
+#[derive(Debug, Clone, Deserialize)]
+struct Payload {
+    name: String,
+    build: Build,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Build {
+    full_url: String,
+    number: u64,
+    phase: Phase,
+    status: Option<String>,
+    /// ミリ秒
+    #[serde(default)]
+    duration: u64,
+    scm: Option<Scm>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+enum Phase {
+    Queued,
+    Started,
+    Completed,
+    Finalized,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Scm {
+    branch: Option<String>,
+}
+
+/// イベントの種類とメッセージを返す
+#[tracing::instrument(target = "wh_handler::jenkins::handle", skip_all)]
+fn handle(_headers: HeaderMap, payload: &str) -> Result<Option<(String, String)>, Failure> {
+    let payload: Payload = parse_payload(payload)?;
+    tracing::info!(
+        job = payload.name,
+        number = payload.build.number,
+        "jenkins: {:?}",
+        payload.build.phase
+    );
+    let Payload { name, build } = payload;
+    let Build {
+        full_url,
+        number,
+        phase,
+        status,
+        duration,
+        scm,
+    } = &build;
+    // `full_url`は`<job url><number>/`になっている
+    let job = match full_url.strip_suffix(&format!("{number}/")) {
+        Some(job_url) => format!("[{name}]({job_url})"),
+        None => name,
+    };
+    let prefix = match scm.as_ref().and_then(|s| s.branch.as_deref()) {
+        Some(branch) => format!("{job}:{branch}"),
+        None => job,
+    };
+    let build = format!("[#{number}]({full_url})");
+    let (event_type, message) = match phase {
+        Phase::Started => ("started", format!("[{prefix}] Build {build} is running\n")),
+        Phase::Completed => {
+            let conclusion = match status.as_deref() {
+                Some("SUCCESS") => "success",
+                Some("FAILURE") => "failed",
+                Some("UNSTABLE") => "unstable",
+                Some("ABORTED") => "aborted",
+                Some("NOT_BUILT") => "not built",
+                Some(_) | None => "unknown",
+            };
+            let duration = if *duration > 0 {
+                format!(" in {}", duration_str(duration / 1000))
+            } else {
+                String::new()
+            };
+            let message = format!(
+                "[{prefix}] Build {build} completed as {conclusion}{duration} ([console]({full_url}console))\n"
+            );
+            ("completed", message)
+        }
+        // QUEUEDは通知するほどではなく、FINALIZEDはCOMPLETEDと重複する
+        Phase::Queued | Phase::Finalized => return Ok(None),
+    };
+    Ok(Some((event_type.to_string(), message)))
+}
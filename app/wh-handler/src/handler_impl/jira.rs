@@ -0,0 +1,297 @@
+use http::HeaderMap;
+use indoc::formatdoc;
+use itertools::Itertools;
+use serde::Deserialize;
+
+use domain::{Event, Failure, Webhook};
+
+use super::Handled;
+use super::utils::{extract_header_value, parse_payload, truncate, verify_hmac_sha256};
+use crate::WebhookHandlerImpl;
+
+/// 引用するコメントの最大文字数
+const MAX_COMMENT_LENGTH: usize = 2000;
+
+impl WebhookHandlerImpl {
+    pub(crate) fn handle_jira(
+        &self,
+        webhook: &Webhook,
+        headers: HeaderMap,
+        payload: &str,
+    ) -> Result<Option<Handled>, Failure> {
+        let Some((event_type, message)) = handle(webhook.secret(), headers, payload)? else {
+            return Ok(None);
+        };
+        let kind = "jira".to_string().into();
+        let event = Event {
+            destination: webhook.destination(),
+            kind,
+            body: message.into(),
+            collapse: false,
+            deliveries: vec![],
+        };
+        Ok(Some(Handled { event, event_type }))
+    }
+}
+
+/// <https://developer.atlassian.com/server/jira/platform/webhooks/>
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Payload {
+    webhook_event: String,
+    user: Option<User>,
+    issue: Option<Issue>,
+    changelog: Option<Changelog>,
+    comment: Option<Comment>,
+    sprint: Option<Sprint>,
+    version: Option<Version>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct User {
+    display_name: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Issue {
+    key: String,
+    #[serde(rename = "self")]
+    self_url: String,
+    fields: IssueFields,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IssueFields {
+    summary: String,
+    issuetype: Option<Named>,
+    priority: Option<Named>,
+    assignee: Option<User>,
+    project: Option<Project>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Named {
+    name: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Project {
+    key: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Changelog {
+    items: Vec<ChangelogItem>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChangelogItem {
+    field: String,
+    from_string: Option<String>,
+    to_string: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Comment {
+    author: Option<User>,
+    body: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Sprint {
+    name: String,
+    start_date: Option<String>,
+    end_date: Option<String>,
+    goal: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Version {
+    name: String,
+    description: Option<String>,
+    release_date: Option<String>,
+}
+
+/// イベントの種類とメッセージを返す
+#[tracing::instrument(target = "wh_handler::jira::handle", skip_all)]
+fn handle(
+    secret: Option<&str>,
+    headers: HeaderMap,
+    payload: &str,
+) -> Result<Option<(String, String)>, Failure> {
+    // https://developer.atlassian.com/cloud/jira/platform/webhooks/#secure-admin-webhooks
+    // Jira Server / Data Center 10未満は署名に対応していないので、シークレットは任意
+    if let Some(secret) = secret {
+        let signature = extract_header_value(&headers, "X-Hub-Signature")?;
+        let signature = signature.strip_prefix(b"sha256=").unwrap_or(signature);
+        verify_hmac_sha256(secret, payload.as_bytes(), signature)?;
+    }
+    let payload: Payload = parse_payload(payload)?;
+    tracing::info!("Jira webhookEvent: {}", payload.webhook_event);
+    let message = match payload.webhook_event.as_str() {
+        "jira:issue_created" => issue_created(&payload),
+        "jira:issue_updated" => issue_updated(&payload),
+        "comment_created" => comment_created(&payload),
+        "sprint_started" => sprint(&payload, "started"),
+        "sprint_closed" => sprint(&payload, "closed"),
+        "jira:version_released" => version_released(&payload),
+        _ => None,
+    };
+    Ok(message.map(|m| (payload.webhook_event, m)))
+}
+
+/// `webhookEvent: jira:issue_created`
+fn issue_created(payload: &Payload) -> Option<String> {
+    let issue = payload.issue.as_ref()?;
+    let IssueFields {
+        issuetype,
+        priority,
+        assignee,
+        ..
+    } = &issue.fields;
+    let detail = [
+        issuetype.as_ref().map(|t| format!("type: {}", t.name)),
+        priority.as_ref().map(|p| format!("priority: {}", p.name)),
+        assignee
+            .as_ref()
+            .map(|a| format!("assignee: {}", a.display_name)),
+    ]
+    .into_iter()
+    .flatten()
+    .join(" / ");
+    let message = formatdoc! {
+        r"
+            [{project}] Issue {issue} created by {user}
+            {detail}
+        ",
+        project = project_str(issue),
+        issue = issue_str(issue),
+        user = user_str(payload.user.as_ref()),
+    };
+    Some(message)
+}
+
+/// `webhookEvent: jira:issue_updated`
+///
+/// status, assignee, priorityの変更のみ通知する
+fn issue_updated(payload: &Payload) -> Option<String> {
+    let issue = payload.issue.as_ref()?;
+    let changes = payload
+        .changelog
+        .iter()
+        .flat_map(|c| &c.items)
+        .filter(|i| matches!(i.field.as_str(), "status" | "assignee" | "priority"))
+        .map(|i| {
+            let from = i.from_string.as_deref().unwrap_or("(none)");
+            let to = i.to_string.as_deref().unwrap_or("(none)");
+            format!("- {}: {from} → {to}", i.field)
+        })
+        .join("\n");
+    if changes.is_empty() {
+        return None;
+    }
+    let message = formatdoc! {
+        r"
+            [{project}] Issue {issue} updated by {user}
+            {changes}
+        ",
+        project = project_str(issue),
+        issue = issue_str(issue),
+        user = user_str(payload.user.as_ref()),
+    };
+    Some(message)
+}
+
+/// `webhookEvent: comment_created`
+fn comment_created(payload: &Payload) -> Option<String> {
+    let issue = payload.issue.as_ref()?;
+    let Comment { author, body } = payload.comment.as_ref()?;
+    let user = author.as_ref().or(payload.user.as_ref());
+    let body = truncate(body.clone(), MAX_COMMENT_LENGTH);
+    let message = formatdoc! {
+        r"
+            [{project}] Issue {issue} commented by {user}
+            {body}
+        ",
+        project = project_str(issue),
+        issue = issue_str(issue),
+        user = user_str(user),
+        body = body.lines().map(|l| format!("> {l}")).join("\n"),
+    };
+    Some(message)
+}
+
+/// `webhookEvent: sprint_started`, `sprint_closed`
+fn sprint(payload: &Payload, action: &str) -> Option<String> {
+    let Sprint {
+        name,
+        start_date,
+        end_date,
+        goal,
+    } = payload.sprint.as_ref()?;
+    let period = match (start_date, end_date) {
+        (Some(start), Some(end)) => format!("{start} - {end}"),
+        _ => String::new(),
+    };
+    let goal = goal
+        .as_deref()
+        .filter(|g| !g.is_empty())
+        .map(|g| format!("goal: {g}"))
+        .unwrap_or_default();
+    let headline = format!("[Jira] Sprint **{name}** {action}");
+    let message = [headline, period, goal]
+        .into_iter()
+        .filter(|s| !s.is_empty())
+        .join("\n");
+    Some(message)
+}
+
+/// `webhookEvent: jira:version_released`
+fn version_released(payload: &Payload) -> Option<String> {
+    let Version {
+        name,
+        description,
+        release_date,
+    } = payload.version.as_ref()?;
+    let release_date = release_date
+        .as_deref()
+        .map(|d| format!(" ({d})"))
+        .unwrap_or_default();
+    let message = formatdoc! {
+        r"
+            [Jira] Version **{name}** released{release_date} by {user}
+            {description}
+        ",
+        user = user_str(payload.user.as_ref()),
+        description = description.as_deref().unwrap_or_default(),
+    };
+    Some(message)
+}
+
+fn user_str(user: Option<&User>) -> &str {
+    user.map_or("someone", |u| &u.display_name)
+}
+
+fn project_str(issue: &Issue) -> &str {
+    issue.fields.project.as_ref().map_or_else(
+        || issue.key.split('-').next().unwrap_or_default(),
+        |p| &p.key,
+    )
+}
+
+/// `self`はREST APIのURLなので、そこからブラウザで開けるURLを作る
+fn issue_str(issue: &Issue) -> String {
+    let Issue { key, self_url, .. } = issue;
+    let base = self_url
+        .split_once("/rest/")
+        .map_or(self_url.as_str(), |(b, _)| b);
+    format!(
+        "[{key} {summary}]({base}/browse/{key})",
+        summary = issue.fields.summary
+    )
+}
@@ -0,0 +1,445 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use http::HeaderMap;
+use indoc::formatdoc;
+use itertools::Itertools;
+use serde::Deserialize;
+use serde_json::Value;
+
+use domain::{Event, Failure, Webhook};
+
+use super::Handled;
+use super::utils::{
+    extract_header_value, parse_payload, parse_value, require_secret, verify_hmac_sha256,
+};
+use crate::WebhookHandlerImpl;
+
+/// `webhookTimestamp`と現在時刻のずれの許容範囲 (ミリ秒)
+///
+/// Linearのドキュメントで推奨されている1分に合わせる
+const TOLERANCE_MILLIS: u64 = 60 * 1000;
+
+impl WebhookHandlerImpl {
+    pub(crate) fn handle_linear(
+        &self,
+        webhook: &Webhook,
+        headers: HeaderMap,
+        payload: &str,
+    ) -> Result<Option<Handled>, Failure> {
+        let Some((event_type, message)) = handle(webhook.secret(), headers, payload)? else {
+            return Ok(None);
+        };
+        let kind = "linear".to_string().into();
+        let event = Event {
+            destination: webhook.destination(),
+            kind,
+            body: message.into(),
+            collapse: false,
+            deliveries: vec![],
+        };
+        Ok(Some(Handled { event, event_type }))
+    }
+}
+
+/// <https://developers.linear.app/docs/graphql/webhooks>
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Payload {
+    action: Action,
+    r#type: String,
+    actor: Option<Named>,
+    url: Option<String>,
+    data: Value,
+    #[serde(default)]
+    updated_from: Value,
+    /// 送信時刻 (UNIXエポックからのミリ秒)。リプレイ攻撃の防止に使う
+    webhook_timestamp: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Action {
+    Create,
+    Update,
+    Remove,
+}
+
+impl Action {
+    /// ペイロードの`action`の値
+    fn raw(self) -> &'static str {
+        match self {
+            Self::Create => "create",
+            Self::Update => "update",
+            Self::Remove => "remove",
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Create => "created",
+            Self::Update => "updated",
+            Self::Remove => "removed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Named {
+    name: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Team {
+    key: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Issue {
+    identifier: String,
+    title: String,
+    url: Option<String>,
+    state: Option<Named>,
+    priority_label: Option<String>,
+    assignee: Option<Named>,
+    team: Option<Team>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Comment {
+    body: String,
+    issue: Option<CommentIssue>,
+    user: Option<Named>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct CommentIssue {
+    identifier: String,
+    title: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Project {
+    name: String,
+    url: Option<String>,
+    state: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Cycle {
+    number: u64,
+    name: Option<String>,
+    starts_at: Option<String>,
+    ends_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct IssueLabel {
+    name: String,
+}
+
+/// イベントの種類とメッセージを返す
+#[tracing::instrument(target = "wh_handler::linear::handle", skip_all)]
+fn handle(
+    secret: Option<&str>,
+    headers: HeaderMap,
+    payload: &str,
+) -> Result<Option<(String, String)>, Failure> {
+    // https://developers.linear.app/docs/graphql/webhooks#securing-webhooks
+    let secret = require_secret(secret)?;
+    let signature = extract_header_value(&headers, "Linear-Signature")?;
+    verify_hmac_sha256(secret, payload.as_bytes(), signature)?;
+    let payload: Payload = parse_payload(payload)?;
+    verify_timestamp(payload.webhook_timestamp, now_millis())?;
+    tracing::info!("Linear: {} {:?}", payload.r#type, payload.action);
+    let event_type = format!("{}.{}", payload.r#type, payload.action.raw());
+    let message = match payload.r#type.as_str() {
+        "Issue" => issue(payload)?,
+        "Comment" => comment(payload)?,
+        "Project" => project(payload)?,
+        "Cycle" => cycle(payload)?,
+        "IssueLabel" => issue_label(payload)?,
+        _ => None,
+    };
+    Ok(message.map(|m| (event_type, m)))
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| u64::try_from(d.as_millis()).unwrap_or(u64::MAX))
+}
+
+/// <https://developers.linear.app/docs/graphql/webhooks#securing-webhooks>
+///
+/// 署名は`webhookTimestamp`を含むペイロード全体にかかっているので、署名の検証後に確認する
+fn verify_timestamp(webhook_timestamp: u64, now_millis: u64) -> Result<(), Failure> {
+    if now_millis.abs_diff(webhook_timestamp) > TOLERANCE_MILLIS {
+        return Err(Failure::reject_unauthorized(
+            "Webhook timestamp is outside the tolerance",
+        ));
+    }
+    Ok(())
+}
+
+/// `type: Issue`
+fn issue(payload: Payload) -> Result<Option<String>, Failure> {
+    let Payload {
+        action,
+        actor,
+        url,
+        data,
+        updated_from,
+        ..
+    } = payload;
+    let issue: Issue = parse_value(data)?;
+    let detail = match action {
+        Action::Create => [
+            issue.state.as_ref().map(|s| format!("state: {}", s.name)),
+            issue
+                .priority_label
+                .as_ref()
+                .map(|p| format!("priority: {p}")),
+            issue
+                .assignee
+                .as_ref()
+                .map(|a| format!("assignee: {}", a.name)),
+        ]
+        .into_iter()
+        .flatten()
+        .join(" / "),
+        Action::Update => {
+            let changes = issue_changes_str(&issue, &updated_from);
+            if changes.is_empty() {
+                return Ok(None);
+            }
+            changes
+        }
+        Action::Remove => String::new(),
+    };
+    let Issue {
+        identifier,
+        title,
+        team,
+        ..
+    } = &issue;
+    let url = issue.url.as_deref().or(url.as_deref()).unwrap_or_default();
+    let message = formatdoc! {
+        r"
+            [{team}] Issue [{identifier} {title}]({url}) {action} by {actor}
+            {detail}
+        ",
+        team = team.as_ref().map_or("Linear", |t| &t.key),
+        action = action.as_str(),
+        actor = actor_str(actor.as_ref()),
+    };
+    Ok(Some(message))
+}
+
+/// `updatedFrom`には変更されたフィールドの変更前の値しか入っていないので、変更後の値は`data`から取る
+///
+/// 変更前のステータスと担当者はIDでしか分からないため、`- state: → Done`のように変更後の値だけを表示する
+fn issue_changes_str(issue: &Issue, from: &Value) -> String {
+    let title = from
+        .get("title")
+        .and_then(Value::as_str)
+        .map(|t| format!("- title: {t} → {}", issue.title));
+    let state = from.get("stateId").map(|_| {
+        let to = issue.state.as_ref().map_or("(none)", |s| &s.name);
+        format!("- state: → {to}")
+    });
+    let assignee = from.get("assigneeId").map(|_| {
+        let to = issue.assignee.as_ref().map_or("(none)", |a| &a.name);
+        format!("- assignee: → {to}")
+    });
+    let priority = from.get("priority").and_then(Value::as_u64).map(|p| {
+        let to = issue.priority_label.as_deref().unwrap_or("(none)");
+        format!("- priority: {} → {to}", priority_str(p))
+    });
+    [title, state, assignee, priority]
+        .into_iter()
+        .flatten()
+        .join("\n")
+}
+
+/// <https://developers.linear.app/docs/graphql/working-with-the-graphql-api#issue-priority>
+fn priority_str(priority: u64) -> &'static str {
+    match priority {
+        1 => "Urgent",
+        2 => "High",
+        3 => "Medium",
+        4 => "Low",
+        _ => "No priority",
+    }
+}
+
+/// `type: Comment`
+fn comment(payload: Payload) -> Result<Option<String>, Failure> {
+    let Payload {
+        action,
+        actor,
+        url,
+        data,
+        ..
+    } = payload;
+    let Comment { body, issue, user } = parse_value(data)?;
+    let issue = issue.map_or_else(String::new, |i| format!("{} {}", i.identifier, i.title));
+    let actor = user.as_ref().or(actor.as_ref());
+    let body = match action {
+        Action::Create | Action::Update => body.lines().map(|l| format!("> {l}")).join("\n"),
+        Action::Remove => String::new(),
+    };
+    let message = formatdoc! {
+        r"
+            [Linear] Comment on [{issue}]({url}) {action} by {actor}
+            {body}
+        ",
+        url = url.as_deref().unwrap_or_default(),
+        action = action.as_str(),
+        actor = actor_str(actor),
+    };
+    Ok(Some(message))
+}
+
+/// `type: Project`
+fn project(payload: Payload) -> Result<Option<String>, Failure> {
+    let Payload {
+        action,
+        actor,
+        url,
+        data,
+        updated_from,
+        ..
+    } = payload;
+    let Project {
+        name,
+        url: project_url,
+        state,
+    } = parse_value(data)?;
+    let detail = match (action, updated_from.get("state").and_then(Value::as_str)) {
+        (Action::Update, Some(from)) => {
+            format!("state: {from} → {}", state.as_deref().unwrap_or("(none)"))
+        }
+        (Action::Update, None) => return Ok(None),
+        _ => state.map(|s| format!("state: {s}")).unwrap_or_default(),
+    };
+    let message = formatdoc! {
+        r"
+            [Linear] Project [{name}]({url}) {action} by {actor}
+            {detail}
+        ",
+        url = project_url.or(url).unwrap_or_default(),
+        action = action.as_str(),
+        actor = actor_str(actor.as_ref()),
+    };
+    Ok(Some(message))
+}
+
+/// `type: Cycle`
+fn cycle(payload: Payload) -> Result<Option<String>, Failure> {
+    let Payload {
+        action,
+        actor,
+        data,
+        ..
+    } = payload;
+    let Cycle {
+        number,
+        name,
+        starts_at,
+        ends_at,
+    } = parse_value(data)?;
+    let name = name.map(|n| format!(" {n}")).unwrap_or_default();
+    let period = match (starts_at, ends_at) {
+        (Some(start), Some(end)) => format!("{start} - {end}"),
+        _ => String::new(),
+    };
+    let message = formatdoc! {
+        r"
+            [Linear] Cycle {number}{name} {action} by {actor}
+            {period}
+        ",
+        action = action.as_str(),
+        actor = actor_str(actor.as_ref()),
+    };
+    Ok(Some(message))
+}
+
+/// `type: IssueLabel`
+fn issue_label(payload: Payload) -> Result<Option<String>, Failure> {
+    let Payload {
+        action,
+        actor,
+        data,
+        ..
+    } = payload;
+    let IssueLabel { name } = parse_value(data)?;
+    let message = format!(
+        "[Linear] Label `{name}` {action} by {actor}\n",
+        action = action.as_str(),
+        actor = actor_str(actor.as_ref()),
+    );
+    Ok(Some(message))
+}
+
+fn actor_str(actor: Option<&Named>) -> &str {
+    actor.map_or("someone", |a| &a.name)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn timestamp_within_tolerance() {
+        let now = 1_700_000_000_000;
+        assert!(verify_timestamp(now, now).is_ok());
+        assert!(verify_timestamp(now - TOLERANCE_MILLIS, now).is_ok());
+        assert!(verify_timestamp(now + TOLERANCE_MILLIS, now).is_ok());
+        let res = verify_timestamp(now - TOLERANCE_MILLIS - 1, now);
+        assert!(matches!(res, Err(Failure::Reject(_))));
+        let res = verify_timestamp(now + TOLERANCE_MILLIS + 1, now);
+        assert!(matches!(res, Err(Failure::Reject(_))));
+    }
+
+    #[test]
+    fn issue_changes_mark_state_and_assignee_as_changed() {
+        let issue: Issue = parse_value(json!({
+            "identifier": "ENG-1",
+            "title": "New title",
+            "state": { "name": "Done" },
+            "priorityLabel": "High",
+            "assignee": null
+        }))
+        .unwrap();
+        let from = json!({
+            "title": "Old title",
+            "stateId": "state-id",
+            "assigneeId": "user-id",
+            "priority": 3
+        });
+        assert_eq!(
+            issue_changes_str(&issue, &from),
+            "- title: Old title → New title\n\
+             - state: → Done\n\
+             - assignee: → (none)\n\
+             - priority: Medium → High"
+        );
+        assert!(issue_changes_str(&issue, &json!({})).is_empty());
+    }
+
+    #[test]
+    fn issue_changes_show_state_change() {
+        let issue: Issue = parse_value(json!({
+            "identifier": "ENG-2",
+            "title": "Fix login",
+            "state": { "name": "In Progress" }
+        }))
+        .unwrap();
+        let from = json!({ "stateId": "todo-state-id", "updatedAt": "2024-01-01T00:00:00Z" });
+        assert_eq!(issue_changes_str(&issue, &from), "- state: → In Progress");
+    }
+}
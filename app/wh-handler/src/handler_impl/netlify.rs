@@ -0,0 +1,225 @@
+use std::str::from_utf8;
+
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use hmac::{Hmac, Mac};
+use http::HeaderMap;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use domain::{Event, Failure, Webhook};
+
+use super::Handled;
+use super::utils::{extract_header_value, parse_payload, require_secret};
+use crate::WebhookHandlerImpl;
+
+impl WebhookHandlerImpl {
+    pub(crate) fn handle_netlify(
+        &self,
+        webhook: &Webhook,
+        headers: HeaderMap,
+        payload: &str,
+    ) -> Result<Option<Handled>, Failure> {
+        let Some((event_type, message)) = handle(webhook.secret(), headers, payload)? else {
+            return Ok(None);
+        };
+        let kind = "netlify".to_string().into();
+        let event = Event {
+            destination: webhook.destination(),
+            kind,
+            body: message.into(),
+            collapse: false,
+            deliveries: vec![],
+        };
+        Ok(Some(Handled { event, event_type }))
+    }
+}
+
+/// <https://docs.netlify.com/site-deploys/deploy-notifications/#outgoing-webhooks>
+///
+/// デプロイのオブジェクトがそのまま送られてくる
+#[derive(Debug, Clone, Deserialize)]
+struct Payload {
+    name: String,
+    state: String,
+    /// `production`, `deploy-preview`, `branch-deploy`
+    context: Option<String>,
+    branch: Option<String>,
+    commit_ref: Option<String>,
+    commit_url: Option<String>,
+    title: Option<String>,
+    committer: Option<String>,
+    ssl_url: Option<String>,
+    deploy_ssl_url: Option<String>,
+    admin_url: Option<String>,
+    error_message: Option<String>,
+}
+
+/// JWSのペイロード
+#[derive(Debug, Clone, Deserialize)]
+struct Claims {
+    iss: String,
+    sha256: String,
+}
+
+/// イベントの種類とメッセージを返す
+#[tracing::instrument(target = "wh_handler::netlify::handle", skip_all)]
+fn handle(
+    secret: Option<&str>,
+    headers: HeaderMap,
+    payload: &str,
+) -> Result<Option<(String, String)>, Failure> {
+    let secret = require_secret(secret)?;
+    let signature = extract_header_value(&headers, "X-Webhook-Signature")?;
+    verify_jws(secret, payload.as_bytes(), signature)?;
+    let payload: Payload = parse_payload(payload)?;
+    tracing::info!("Netlify: {} {}", payload.name, payload.state);
+    let action = match payload.state.as_str() {
+        "building" => ":rocket: Deploy started",
+        "ready" => ":white_check_mark: Deploy succeeded",
+        "error" => ":x: Deploy failed",
+        // enqueued, uploading, processing, ...
+        _ => return Ok(None),
+    };
+    let message = deploy_str(action, &payload);
+    Ok(Some((payload.state, message)))
+}
+
+/// <https://docs.netlify.com/site-deploys/deploy-notifications/#payload-signature>
+///
+/// HS256で署名されたJWSで、クレームの`sha256`にペイロードのハッシュが入る
+fn verify_jws(secret: &str, payload: &[u8], signature: &[u8]) -> Result<(), Failure> {
+    let malformed = || Failure::reject_unauthorized("Received malformed signature");
+    let token = from_utf8(signature).map_err(|_| malformed())?;
+    let (signed, mac) = token.rsplit_once('.').ok_or_else(malformed)?;
+    let (_header, claims) = signed.split_once('.').ok_or_else(malformed)?;
+    let mac = URL_SAFE_NO_PAD.decode(mac).map_err(|_| malformed())?;
+    let mut expected = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .map_err(|e| anyhow::anyhow!("Failed to initialize HMAC: {e}"))?;
+    expected.update(signed.as_bytes());
+    expected
+        .verify_slice(&mac)
+        .map_err(|_| Failure::reject_unauthorized("Signature mismatch"))?;
+    let claims = URL_SAFE_NO_PAD.decode(claims).map_err(|_| malformed())?;
+    let claims: Claims = serde_json::from_slice(&claims).map_err(|_| malformed())?;
+    let digest = hex::encode(Sha256::digest(payload));
+    if claims.iss != "netlify" || claims.sha256 != digest {
+        return Err(Failure::reject_unauthorized("Signature mismatch"));
+    }
+    Ok(())
+}
+
+fn deploy_str(action: &str, payload: &Payload) -> String {
+    let Payload {
+        name,
+        context,
+        branch,
+        commit_ref,
+        commit_url,
+        title,
+        committer,
+        ssl_url,
+        deploy_ssl_url,
+        admin_url,
+        error_message,
+        ..
+    } = payload;
+    let site = match admin_url {
+        Some(admin_url) => format!("[{name}]({admin_url})"),
+        None => name.clone(),
+    };
+    let branch = branch
+        .as_deref()
+        .map(|b| format!(":{b}"))
+        .unwrap_or_default();
+    let target = match context.as_deref() {
+        Some("production") | None => "production",
+        Some(_) => "preview",
+    };
+    let commit = commit_ref.as_deref().map(|sha| {
+        let short = sha.get(..7).unwrap_or(sha);
+        let sha = match commit_url {
+            Some(url) => format!("[`{short}`]({url})"),
+            None => format!("`{short}`"),
+        };
+        let title = title
+            .as_deref()
+            .and_then(|t| t.lines().next())
+            .unwrap_or_default();
+        let author = committer
+            .as_deref()
+            .map(|c| format!(" by {c}"))
+            .unwrap_or_default();
+        format!("{sha} {title}{author}")
+    });
+    // productionはサイトのURL、previewはデプロイごとのURL
+    let url = match target {
+        "production" => ssl_url.as_ref().or(deploy_ssl_url.as_ref()),
+        _ => deploy_ssl_url.as_ref().or(ssl_url.as_ref()),
+    };
+    let mut message = format!("[{site}{branch}] {action} to {target}\n");
+    if let Some(commit) = commit {
+        message += &format!("{commit}\n");
+    }
+    if let Some(error_message) = error_message.as_deref().filter(|e| !e.is_empty()) {
+        message += &format!("> {error_message}\n");
+    }
+    if let Some(url) = url {
+        message += &format!("{url}\n");
+    }
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "netlify-secret";
+    const PAYLOAD: &[u8] = br#"{"name":"site","state":"ready"}"#;
+
+    fn jws(secret: &str, claims: &str) -> String {
+        let header = URL_SAFE_NO_PAD.encode(r#"{"alg":"HS256","typ":"JWT"}"#);
+        let signed = format!("{header}.{}", URL_SAFE_NO_PAD.encode(claims));
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(signed.as_bytes());
+        format!(
+            "{signed}.{}",
+            URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes())
+        )
+    }
+
+    fn claims(iss: &str, payload: &[u8]) -> String {
+        let digest = hex::encode(Sha256::digest(payload));
+        format!(r#"{{"iss":"{iss}","sha256":"{digest}"}}"#)
+    }
+
+    #[test]
+    fn accepts_valid_jws() {
+        let token = jws(SECRET, &claims("netlify", PAYLOAD));
+        assert!(verify_jws(SECRET, PAYLOAD, token.as_bytes()).is_ok());
+    }
+
+    #[test]
+    fn rejects_wrong_secret() {
+        let token = jws("other", &claims("netlify", PAYLOAD));
+        let res = verify_jws(SECRET, PAYLOAD, token.as_bytes());
+        assert!(matches!(res, Err(Failure::Reject(_))));
+    }
+
+    #[test]
+    fn rejects_mismatched_claims() {
+        let token = jws(SECRET, &claims("netlify", b"{}"));
+        let res = verify_jws(SECRET, PAYLOAD, token.as_bytes());
+        assert!(matches!(res, Err(Failure::Reject(_))));
+        let token = jws(SECRET, &claims("someone", PAYLOAD));
+        let res = verify_jws(SECRET, PAYLOAD, token.as_bytes());
+        assert!(matches!(res, Err(Failure::Reject(_))));
+    }
+
+    #[test]
+    fn rejects_malformed_token() {
+        for token in ["", "a.b", "a.b.!!!", "a.!!!.c"] {
+            let res = verify_jws(SECRET, PAYLOAD, token.as_bytes());
+            assert!(matches!(res, Err(Failure::Reject(_))), "{token}");
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use serde_json::Value;
+
+use domain::RuleField;
+
+/// ラベルが入っている配列のJSON Pointer
+const LABEL_ARRAYS: [&str; 2] = ["/issue/labels", "/pull_request/labels"];
+
+/// 結果が入っているJSON Pointer。GitHubのworkflow_run, workflow_job, check_run, check_suite
+const CONCLUSIONS: [&str; 4] = [
+    "/workflow_run/conclusion",
+    "/workflow_job/conclusion",
+    "/check_run/conclusion",
+    "/check_suite/conclusion",
+];
+
+/// ルールの評価に使うペイロードの値
+#[derive(Debug, Clone, Default)]
+pub(super) struct Facts {
+    event: String,
+    repo: Option<String>,
+    labels: Vec<String>,
+    conclusion: Option<String>,
+}
+
+impl Facts {
+    /// `payload`がJSONでなければ`event`以外は空になる
+    pub(super) fn new(event_type: &str, payload: &str) -> Self {
+        let event = event_type.to_string();
+        let Ok(payload) = serde_json::from_str::<Value>(payload) else {
+            return Self {
+                event,
+                ..Default::default()
+            };
+        };
+        let str_at = |pointer: &str| payload.pointer(pointer)?.as_str().map(ToString::to_string);
+        // Docker Hubは`repository.repo_name`
+        let repo = str_at("/repository/full_name").or_else(|| str_at("/repository/repo_name"));
+        // labeled, unlabeledでは変更されたラベルが`label`に入る
+        let labels = LABEL_ARRAYS
+            .iter()
+            .filter_map(|p| payload.pointer(p)?.as_array())
+            .flatten()
+            .filter_map(|l| l.get("name")?.as_str())
+            .chain(str_at("/label/name").as_deref())
+            .map(ToString::to_string)
+            .collect();
+        let conclusion = CONCLUSIONS.iter().find_map(|p| str_at(p));
+        Self {
+            event,
+            repo,
+            labels,
+            conclusion,
+        }
+    }
+
+    pub(super) fn values(&self, field: RuleField) -> Vec<String> {
+        match field {
+            RuleField::Repo => self.repo.iter().cloned().collect(),
+            RuleField::Label => self.labels.clone(),
+            RuleField::Conclusion => self.conclusion.iter().cloned().collect(),
+            RuleField::Event => vec![self.event.clone()],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_github_issue() {
+        let payload = r#"{
+            "repository": { "full_name": "org/app" },
+            "issue": { "labels": [{ "name": "bug" }, { "name": "p1" }] },
+            "label": { "name": "triage" }
+        }"#;
+        let facts = Facts::new("issues", payload);
+        assert_eq!(facts.values(RuleField::Event), ["issues"]);
+        assert_eq!(facts.values(RuleField::Repo), ["org/app"]);
+        assert_eq!(facts.values(RuleField::Label), ["bug", "p1", "triage"]);
+        assert!(facts.values(RuleField::Conclusion).is_empty());
+    }
+
+    #[test]
+    fn reads_workflow_conclusion() {
+        let payload = r#"{
+            "repository": { "full_name": "org/app" },
+            "workflow_run": { "conclusion": "failure" }
+        }"#;
+        let facts = Facts::new("workflow_run", payload);
+        assert_eq!(facts.values(RuleField::Conclusion), ["failure"]);
+    }
+
+    #[test]
+    fn reads_docker_hub_repo_name() {
+        let payload = r#"{ "repository": { "repo_name": "org/image" } }"#;
+        let facts = Facts::new("push", payload);
+        assert_eq!(facts.values(RuleField::Repo), ["org/image"]);
+    }
+
+    #[test]
+    fn non_json_payload_has_only_event() {
+        let facts = Facts::new("text", "deploy finished");
+        assert_eq!(facts.values(RuleField::Event), ["text"]);
+        assert!(facts.values(RuleField::Repo).is_empty());
+        assert!(facts.values(RuleField::Label).is_empty());
+        assert!(facts.values(RuleField::Conclusion).is_empty());
+    }
+}
@@ -0,0 +1,273 @@
+use std::str::from_utf8;
+
+use http::HeaderMap;
+use indoc::formatdoc;
+use serde::Deserialize;
+use serde_json::Value;
+
+use domain::{Event, Failure, Webhook};
+
+use super::Handled;
+use super::utils::{
+    extract_header_value, header_str, parse_payload, parse_value, require_secret,
+    verify_hmac_sha256,
+};
+use crate::WebhookHandlerImpl;
+
+impl WebhookHandlerImpl {
+    pub(crate) fn handle_sentry(
+        &self,
+        webhook: &Webhook,
+        headers: HeaderMap,
+        payload: &str,
+    ) -> Result<Option<Handled>, Failure> {
+        let event_type =
+            header_str(&headers, "Sentry-Hook-Resource").unwrap_or_else(|| "sentry".to_string());
+        let Some(message) = handle(webhook.secret(), headers, payload)? else {
+            return Ok(None);
+        };
+        let kind = "sentry".to_string().into();
+        let event = Event {
+            destination: webhook.destination(),
+            kind,
+            body: message.into(),
+            collapse: false,
+            deliveries: vec![],
+        };
+        Ok(Some(Handled { event, event_type }))
+    }
+}
+
+#[tracing::instrument(target = "wh_handler::sentry::handle", skip_all)]
+fn handle(
+    secret: Option<&str>,
+    headers: HeaderMap,
+    payload: &str,
+) -> Result<Option<String>, Failure> {
+    // https://docs.sentry.io/organization/integrations/integration-platform/webhooks/#sentry-hook-signature
+    let secret = require_secret(secret)?;
+    let signature = extract_header_value(&headers, "Sentry-Hook-Signature")?;
+    verify_hmac_sha256(secret, payload.as_bytes(), signature)?;
+    let resource = extract_header_value(&headers, "Sentry-Hook-Resource").and_then(|v| {
+        from_utf8(v).map_err(|e| {
+            let message = format!("Received invalid Sentry-Hook-Resource: {e}");
+            Failure::reject_bad_request(message)
+        })
+    })?;
+    tracing::info!("Sentry-Hook-Resource: {resource}");
+    let payload: Payload = parse_payload(payload)?;
+    let message = match resource {
+        "issue" => issue(payload)?,
+        "error" => error(payload)?,
+        "event_alert" => event_alert(payload)?,
+        "metric_alert" => metric_alert(payload)?,
+        // installation, comment, ...
+        _ => None,
+    };
+    Ok(message)
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Payload {
+    action: String,
+    data: Value,
+    actor: Option<Actor>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Actor {
+    name: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct IssueData {
+    issue: Issue,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Issue {
+    short_id: String,
+    title: String,
+    culprit: Option<String>,
+    level: Option<String>,
+    permalink: Option<String>,
+    web_url: Option<String>,
+    project: Project,
+    assigned_to: Option<Actor>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Project {
+    slug: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ErrorData {
+    error: ErrorEvent,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct EventAlertData {
+    event: ErrorEvent,
+    triggered_rule: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ErrorEvent {
+    title: String,
+    culprit: Option<String>,
+    level: Option<String>,
+    web_url: String,
+    project: Value,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct MetricAlertData {
+    metric_alert: MetricAlert,
+    description_title: String,
+    description_text: String,
+    web_url: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct MetricAlert {
+    alert_rule: AlertRule,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct AlertRule {
+    projects: Vec<String>,
+}
+
+/// `Sentry-Hook-Resource: issue`
+fn issue(payload: Payload) -> Result<Option<String>, Failure> {
+    let Payload {
+        action,
+        data,
+        actor,
+    } = payload;
+    let IssueData { issue } = parse_value(data)?;
+    let action = match action.as_str() {
+        "assigned" => {
+            let assignee = issue.assigned_to.as_ref().map_or("someone", |a| &a.name);
+            format!("assigned to {assignee}")
+        }
+        "created" | "resolved" | "ignored" | "archived" | "unresolved" => action,
+        _ => return Ok(None),
+    };
+    let Issue {
+        short_id,
+        title,
+        culprit,
+        level,
+        permalink,
+        web_url,
+        project,
+        ..
+    } = &issue;
+    let url = permalink
+        .as_deref()
+        .or(web_url.as_deref())
+        .unwrap_or_default();
+    let message = formatdoc! {
+        r"
+            [{project}] Issue [{short_id} {title}]({url}) {action} by {actor}
+            {detail}
+        ",
+        project = project.slug,
+        actor = actor_str(actor.as_ref()),
+        detail = detail_str(level.as_deref(), culprit.as_deref()),
+    };
+    Ok(Some(message))
+}
+
+/// `Sentry-Hook-Resource: error`
+fn error(payload: Payload) -> Result<Option<String>, Failure> {
+    let ErrorData { error } = parse_value(payload.data)?;
+    let ErrorEvent {
+        title,
+        culprit,
+        level,
+        web_url,
+        project,
+    } = &error;
+    let message = formatdoc! {
+        r"
+            [{project}] Error [{title}]({web_url}) occurred
+            {detail}
+        ",
+        project = project_str(project),
+        detail = detail_str(level.as_deref(), culprit.as_deref()),
+    };
+    Ok(Some(message))
+}
+
+/// `Sentry-Hook-Resource: event_alert`
+fn event_alert(payload: Payload) -> Result<Option<String>, Failure> {
+    let EventAlertData {
+        event,
+        triggered_rule,
+    } = parse_value(payload.data)?;
+    let ErrorEvent {
+        title,
+        culprit,
+        level,
+        web_url,
+        project,
+    } = &event;
+    let message = formatdoc! {
+        r"
+            [{project}] :rotating_light: Alert `{triggered_rule}` triggered: [{title}]({web_url})
+            {detail}
+        ",
+        project = project_str(project),
+        detail = detail_str(level.as_deref(), culprit.as_deref()),
+    };
+    Ok(Some(message))
+}
+
+/// `Sentry-Hook-Resource: metric_alert`
+fn metric_alert(payload: Payload) -> Result<Option<String>, Failure> {
+    let Payload { action, data, .. } = payload;
+    let MetricAlertData {
+        metric_alert,
+        description_title,
+        description_text,
+        web_url,
+    } = parse_value(data)?;
+    let state = match action.as_str() {
+        "critical" => ":rotating_light: critical",
+        "warning" => ":warning: warning",
+        "resolved" => ":white_check_mark: resolved",
+        _ => return Ok(None),
+    };
+    let projects = metric_alert.alert_rule.projects.join(", ");
+    let message = formatdoc! {
+        r"
+            [{projects}] Metric alert [{description_title}]({web_url}) {state}
+            {description_text}
+        "
+    };
+    Ok(Some(message))
+}
+
+fn actor_str(actor: Option<&Actor>) -> &str {
+    actor.map_or("Sentry", |a| &a.name)
+}
+
+/// `project`はslugではなくIDで送られてくる
+fn project_str(project: &Value) -> String {
+    match project {
+        Value::String(s) => s.clone(),
+        v => format!("project {v}"),
+    }
+}
+
+fn detail_str(level: Option<&str>, culprit: Option<&str>) -> String {
+    let level = level.unwrap_or("unknown");
+    match culprit {
+        Some(culprit) if !culprit.is_empty() => format!("level: {level} / culprit: `{culprit}`"),
+        _ => format!("level: {level}"),
+    }
+}
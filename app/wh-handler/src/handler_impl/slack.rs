@@ -0,0 +1,357 @@
+use http::HeaderMap;
+use itertools::Itertools;
+use serde::Deserialize;
+
+use domain::{Event, Failure, Webhook};
+
+use super::Handled;
+use super::utils::{color_emoji, parse_payload};
+use crate::WebhookHandlerImpl;
+
+impl WebhookHandlerImpl {
+    pub(crate) fn handle_slack(
+        &self,
+        webhook: &Webhook,
+        headers: HeaderMap,
+        payload: &str,
+    ) -> Result<Option<Handled>, Failure> {
+        let Some(message) = handle(headers, payload)? else {
+            return Ok(None);
+        };
+        let event_type = "slack".to_string();
+        let kind = event_type.clone().into();
+        let event = Event {
+            destination: webhook.destination(),
+            kind,
+            body: message.into(),
+            collapse: false,
+            deliveries: vec![],
+        };
+        Ok(Some(Handled { event, event_type }))
+    }
+}
+
+/// <https://api.slack.com/messaging/webhooks>
+#[derive(Debug, Clone, Deserialize)]
+struct Payload {
+    username: Option<String>,
+    #[serde(default)]
+    text: String,
+    #[serde(default)]
+    blocks: Vec<Block>,
+    #[serde(default)]
+    attachments: Vec<Attachment>,
+}
+
+/// <https://api.slack.com/reference/block-kit/blocks>
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Block {
+    Header {
+        text: TextObject,
+    },
+    Section {
+        text: Option<TextObject>,
+        #[serde(default)]
+        fields: Vec<TextObject>,
+    },
+    Context {
+        elements: Vec<ContextElement>,
+    },
+    Image {
+        image_url: String,
+        #[serde(default)]
+        alt_text: String,
+    },
+    Divider,
+    #[serde(other)]
+    Unsupported,
+}
+
+/// <https://api.slack.com/reference/block-kit/composition-objects#text>
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum TextObject {
+    PlainText { text: String },
+    Mrkdwn { text: String },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ContextElement {
+    PlainText {
+        text: String,
+    },
+    Mrkdwn {
+        text: String,
+    },
+    Image {
+        image_url: String,
+        #[serde(default)]
+        alt_text: String,
+    },
+    #[serde(other)]
+    Unsupported,
+}
+
+/// <https://api.slack.com/reference/messaging/attachments>
+#[derive(Debug, Clone, Deserialize)]
+struct Attachment {
+    color: Option<String>,
+    pretext: Option<String>,
+    author_name: Option<String>,
+    author_link: Option<String>,
+    title: Option<String>,
+    title_link: Option<String>,
+    text: Option<String>,
+    #[serde(default)]
+    fields: Vec<AttachmentField>,
+    #[serde(default)]
+    blocks: Vec<Block>,
+    image_url: Option<String>,
+    footer: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct AttachmentField {
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    value: String,
+}
+
+#[tracing::instrument(target = "wh_handler::slack::handle", skip_all)]
+fn handle(_headers: HeaderMap, payload: &str) -> Result<Option<String>, Failure> {
+    let payload: Payload = parse_payload(payload)?;
+    let Payload {
+        username,
+        text,
+        blocks,
+        attachments,
+    } = payload;
+    // blocksがある場合、textは通知用のfallbackとして扱われる
+    let main = if blocks.is_empty() {
+        mrkdwn_to_markdown(&text)
+    } else {
+        blocks_str(&blocks)
+    };
+    let attachments = attachments.iter().map(attachment_str);
+    let message = username
+        .map(|u| format!("[{u}]"))
+        .into_iter()
+        .chain(std::iter::once(main))
+        .chain(attachments)
+        .filter(|s| !s.trim().is_empty())
+        .join("\n");
+    if message.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(message))
+}
+
+fn blocks_str(blocks: &[Block]) -> String {
+    blocks.iter().filter_map(block_str).join("\n")
+}
+
+fn block_str(block: &Block) -> Option<String> {
+    let s = match block {
+        Block::Header { text } => format!("### {}", text_object_str(text)),
+        Block::Section { text, fields } => {
+            let fields = fields.iter().map(|f| format!("- {}", text_object_str(f)));
+            text.iter().map(text_object_str).chain(fields).join("\n")
+        }
+        Block::Context { elements } => elements.iter().filter_map(context_str).join(" "),
+        Block::Image {
+            image_url,
+            alt_text,
+        } => format!("[{alt_text}]({image_url})"),
+        Block::Divider => "---".to_string(),
+        Block::Unsupported => return None,
+    };
+    Some(s)
+}
+
+fn text_object_str(text: &TextObject) -> String {
+    match text {
+        TextObject::PlainText { text } => text.clone(),
+        TextObject::Mrkdwn { text } => mrkdwn_to_markdown(text),
+    }
+}
+
+fn context_str(element: &ContextElement) -> Option<String> {
+    let s = match element {
+        ContextElement::PlainText { text } => text.clone(),
+        ContextElement::Mrkdwn { text } => mrkdwn_to_markdown(text),
+        ContextElement::Image {
+            image_url,
+            alt_text,
+        } => format!("[{alt_text}]({image_url})"),
+        ContextElement::Unsupported => return None,
+    };
+    Some(s)
+}
+
+fn attachment_str(attachment: &Attachment) -> String {
+    let Attachment {
+        color,
+        pretext,
+        author_name,
+        author_link,
+        title,
+        title_link,
+        text,
+        fields,
+        blocks,
+        image_url,
+        footer,
+    } = attachment;
+    let author = author_name.as_deref().map(|name| match author_link {
+        Some(link) => format!("[{name}]({link})"),
+        None => name.to_string(),
+    });
+    let title = title.as_deref().map(|title| {
+        let title = mrkdwn_to_markdown(title);
+        match title_link {
+            Some(link) => format!("**[{title}]({link})**"),
+            None => format!("**{title}**"),
+        }
+    });
+    let headline = color_prefix(color.as_deref())
+        .into_iter()
+        .map(str::to_string)
+        .chain(author)
+        .chain(title)
+        .join(" ");
+    let fields = fields
+        .iter()
+        .map(|f| format!("- **{}**: {}", f.title, mrkdwn_to_markdown(&f.value)));
+    pretext
+        .iter()
+        .map(|p| mrkdwn_to_markdown(p))
+        .chain(std::iter::once(headline))
+        .chain(text.iter().map(|t| mrkdwn_to_markdown(t)))
+        .chain(fields)
+        .chain(std::iter::once(blocks_str(blocks)))
+        .chain(image_url.clone())
+        .chain(footer.iter().map(|f| mrkdwn_to_markdown(f)))
+        .filter(|s| !s.is_empty())
+        .join("\n")
+}
+
+/// attachmentの`color`を近い色の絵文字にする
+fn color_prefix(color: Option<&str>) -> Option<&'static str> {
+    let prefix = match color? {
+        "good" => ":large_green_circle:",
+        "warning" => ":large_yellow_circle:",
+        "danger" => ":red_circle:",
+        hex => color_emoji(u32::from_str_radix(hex.trim_start_matches('#'), 16).ok()?),
+    };
+    Some(prefix)
+}
+
+/// Slackのmrkdwnをtraqのmarkdownに変換する
+///
+/// <https://api.slack.com/reference/surfaces/formatting>
+fn mrkdwn_to_markdown(text: &str) -> String {
+    // コードブロックとインラインコードの中身は変換しない
+    text.split("```")
+        .enumerate()
+        .map(|(i, block)| {
+            if i % 2 == 1 {
+                return unescape(block);
+            }
+            block
+                .split('`')
+                .enumerate()
+                .map(|(j, inline)| {
+                    if j % 2 == 1 {
+                        return unescape(inline);
+                    }
+                    let s = convert_emphasis(inline, '*', "**");
+                    let s = convert_emphasis(&s, '_', "*");
+                    let s = convert_emphasis(&s, '~', "~~");
+                    convert_angle_brackets(&s)
+                })
+                .join("`")
+        })
+        .join("```")
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+/// `<url|text>`, `<@U123>`, `<#C123|channel>`, `<!here>` などを変換する
+fn convert_angle_brackets(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('<') {
+        result += &unescape(&rest[..start]);
+        let Some(len) = rest[start..].find('>') else {
+            rest = &rest[start..];
+            break;
+        };
+        let inner = &rest[start + 1..start + len];
+        let (target, label) = match inner.split_once('|') {
+            Some((t, l)) => (t, Some(l)),
+            None => (inner, None),
+        };
+        let converted = match (target.chars().next(), label) {
+            (Some('@'), Some(label)) => format!("@{}", label.trim_start_matches('@')),
+            (Some('#'), Some(label)) => format!("#{label}"),
+            // `<!date^...|fallback>`, `<!subteam^...|@handle>`
+            (Some('!'), Some(label)) => unescape(label),
+            (Some('!'), None) => format!("@{}", &target[1..]),
+            (Some('@' | '#'), None) => target.to_string(),
+            (_, Some(label)) => format!("[{}]({target})", unescape(label)),
+            (_, None) => target.to_string(),
+        };
+        result += &converted;
+        rest = &rest[start + len + 1..];
+    }
+    result += &unescape(rest);
+    result
+}
+
+/// `*bold*` → `**bold**` のように、`delim`で囲まれた部分の囲みを`replacement`に置き換える
+///
+/// URLを壊さないよう、`<...>`の中身はそのまま残す
+fn convert_emphasis(text: &str, delim: char, replacement: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut result = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i] == '<'
+            && let Some(len) = chars[i..].iter().position(|&c| c == '>')
+        {
+            result.extend(&chars[i..=i + len]);
+            i += len + 1;
+            continue;
+        }
+        let opens = chars[i] == delim
+            && (i == 0 || !chars[i - 1].is_alphanumeric())
+            && chars.get(i + 1).is_some_and(|c| !c.is_whitespace());
+        let close = opens
+            .then(|| {
+                (i + 2..chars.len()).find(|&j| {
+                    chars[j] == delim
+                        && !chars[j - 1].is_whitespace()
+                        && chars.get(j + 1).is_none_or(|c| !c.is_alphanumeric())
+                })
+            })
+            .flatten()
+            .filter(|&j| !chars[i + 1..j].contains(&'\n'));
+        if let Some(j) = close {
+            result += replacement;
+            result.extend(&chars[i + 1..j]);
+            result += replacement;
+            i = j + 1;
+        } else {
+            result.push(chars[i]);
+            i += 1;
+        }
+    }
+    result
+}
@@ -0,0 +1,471 @@
+use std::str::from_utf8;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use http::HeaderMap;
+use serde::Deserialize;
+use serde_json::Value;
+
+use domain::{Event, Failure, Webhook};
+
+use super::Handled;
+use super::utils::{
+    extract_header_value, parse_payload, parse_value, require_secret, verify_hmac_sha256,
+};
+use crate::WebhookHandlerImpl;
+
+/// 署名のタイムスタンプと現在時刻のずれの許容範囲 (秒)
+///
+/// Stripeの公式ライブラリのデフォルトに合わせる
+const TOLERANCE_SECS: u64 = 300;
+
+impl WebhookHandlerImpl {
+    pub(crate) fn handle_stripe(
+        &self,
+        webhook: &Webhook,
+        headers: HeaderMap,
+        payload: &str,
+    ) -> Result<Option<Handled>, Failure> {
+        let Some((event_type, message)) = handle(webhook.secret(), headers, payload)? else {
+            return Ok(None);
+        };
+        let kind = "stripe".to_string().into();
+        let event = Event {
+            destination: webhook.destination(),
+            kind,
+            body: message.into(),
+            collapse: false,
+            deliveries: vec![],
+        };
+        Ok(Some(Handled { event, event_type }))
+    }
+}
+
+/// <https://docs.stripe.com/api/events/object>
+#[derive(Debug, Clone, Deserialize)]
+struct Payload {
+    r#type: String,
+    livemode: bool,
+    data: Data,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Data {
+    object: Value,
+}
+
+/// <https://docs.stripe.com/api/invoices/object>
+#[derive(Debug, Clone, Deserialize)]
+struct Invoice {
+    id: String,
+    number: Option<String>,
+    customer_email: Option<String>,
+    customer_name: Option<String>,
+    amount_due: i64,
+    amount_paid: i64,
+    currency: String,
+    attempt_count: Option<u64>,
+    hosted_invoice_url: Option<String>,
+}
+
+/// <https://docs.stripe.com/api/subscriptions/object>
+#[derive(Debug, Clone, Deserialize)]
+struct Subscription {
+    id: String,
+    customer: String,
+    status: String,
+    items: List<SubscriptionItem>,
+    cancellation_details: Option<CancellationDetails>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct List<T> {
+    data: Vec<T>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct SubscriptionItem {
+    price: Price,
+    quantity: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Price {
+    nickname: Option<String>,
+    unit_amount: Option<i64>,
+    currency: String,
+    recurring: Option<Recurring>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Recurring {
+    interval: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct CancellationDetails {
+    reason: Option<String>,
+}
+
+/// <https://docs.stripe.com/api/disputes/object>
+#[derive(Debug, Clone, Deserialize)]
+struct Dispute {
+    id: String,
+    amount: i64,
+    currency: String,
+    reason: String,
+    evidence_details: Option<EvidenceDetails>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct EvidenceDetails {
+    due_by: Option<u64>,
+}
+
+/// <https://docs.stripe.com/api/charges/object>
+#[derive(Debug, Clone, Deserialize)]
+struct Charge {
+    id: String,
+    amount: i64,
+    amount_refunded: i64,
+    currency: String,
+    billing_details: Option<BillingDetails>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct BillingDetails {
+    email: Option<String>,
+}
+
+/// イベントの種類とメッセージを返す
+#[tracing::instrument(target = "wh_handler::stripe::handle", skip_all)]
+fn handle(
+    secret: Option<&str>,
+    headers: HeaderMap,
+    payload: &str,
+) -> Result<Option<(String, String)>, Failure> {
+    let secret = require_secret(secret)?;
+    let signature = extract_header_value(&headers, "Stripe-Signature")?;
+    verify_signature(secret, payload, signature, now())?;
+    let payload: Payload = parse_payload(payload)?;
+    tracing::info!("Stripe: {}", payload.r#type);
+    let Payload {
+        r#type,
+        livemode,
+        data: Data { object },
+    } = payload;
+    let dashboard = Dashboard { livemode };
+    let message = match r#type.as_str() {
+        "invoice.payment_failed" => invoice_payment_failed(&dashboard, object)?,
+        "invoice.paid" => invoice_paid(&dashboard, object)?,
+        "customer.subscription.created" => subscription_created(&dashboard, object)?,
+        "customer.subscription.deleted" => subscription_deleted(&dashboard, object)?,
+        "charge.dispute.created" => dispute_created(&dashboard, object)?,
+        "charge.refunded" => charge_refunded(&dashboard, object)?,
+        // その他のイベントは無視する
+        _ => return Ok(None),
+    };
+    Ok(Some((r#type, message)))
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// <https://docs.stripe.com/webhooks#verify-manually>
+///
+/// `Stripe-Signature: t=<timestamp>,v1=<signature>,v1=<signature>,...`
+fn verify_signature(secret: &str, payload: &str, header: &[u8], now: u64) -> Result<(), Failure> {
+    let malformed = || Failure::reject_unauthorized("Received malformed signature");
+    let header = from_utf8(header).map_err(|_| malformed())?;
+    let pairs = header.split(',').filter_map(|p| p.trim().split_once('='));
+    let mut timestamp = None;
+    let mut signatures = vec![];
+    for (key, value) in pairs {
+        match key {
+            "t" => timestamp = Some(value),
+            "v1" => signatures.push(value),
+            // v0はテスト用の古い形式
+            _ => {}
+        }
+    }
+    let timestamp = timestamp.ok_or_else(malformed)?;
+    let signed_at: u64 = timestamp.parse().map_err(|_| malformed())?;
+    if now.abs_diff(signed_at) > TOLERANCE_SECS {
+        return Err(Failure::reject_unauthorized(
+            "Signature timestamp is outside the tolerance",
+        ));
+    }
+    let signed_payload = format!("{timestamp}.{payload}");
+    // シークレットのローテーション中は複数の署名が付く
+    let verified = signatures
+        .iter()
+        .any(|s| verify_hmac_sha256(secret, signed_payload.as_bytes(), s.as_bytes()).is_ok());
+    if !verified {
+        return Err(Failure::reject_unauthorized("Signature mismatch"));
+    }
+    Ok(())
+}
+
+/// ダッシュボードへのリンク
+struct Dashboard {
+    livemode: bool,
+}
+
+impl Dashboard {
+    fn link(&self, text: &str, path: &str, id: &str) -> String {
+        let mode = if self.livemode { "" } else { "test/" };
+        format!("[{text}](https://dashboard.stripe.com/{mode}{path}/{id})")
+    }
+
+    fn prefix(&self) -> &'static str {
+        if self.livemode {
+            "[Stripe]"
+        } else {
+            "[Stripe (test)]"
+        }
+    }
+}
+
+/// `invoice.payment_failed`
+fn invoice_payment_failed(dashboard: &Dashboard, object: Value) -> Result<String, Failure> {
+    let invoice: Invoice = parse_value(object)?;
+    let link = invoice_link(dashboard, &invoice);
+    let amount = amount_str(invoice.amount_due, &invoice.currency);
+    let customer = customer_str(&invoice);
+    let attempt = invoice
+        .attempt_count
+        .map(|c| format!(" (attempt {c})"))
+        .unwrap_or_default();
+    let prefix = dashboard.prefix();
+    Ok(format!(
+        "{prefix} :x: Payment of {amount} for invoice {link} failed{attempt}{customer}\n"
+    ))
+}
+
+/// `invoice.paid`
+fn invoice_paid(dashboard: &Dashboard, object: Value) -> Result<String, Failure> {
+    let invoice: Invoice = parse_value(object)?;
+    let link = invoice_link(dashboard, &invoice);
+    let amount = amount_str(invoice.amount_paid, &invoice.currency);
+    let customer = customer_str(&invoice);
+    let prefix = dashboard.prefix();
+    Ok(format!(
+        "{prefix} :white_check_mark: Invoice {link} was paid: {amount}{customer}\n"
+    ))
+}
+
+fn invoice_link(dashboard: &Dashboard, invoice: &Invoice) -> String {
+    let text = invoice.number.as_deref().unwrap_or(&invoice.id);
+    let link = dashboard.link(text, "invoices", &invoice.id);
+    match &invoice.hosted_invoice_url {
+        Some(url) => format!("{link} ([invoice page]({url}))"),
+        None => link,
+    }
+}
+
+fn customer_str(invoice: &Invoice) -> String {
+    invoice
+        .customer_name
+        .as_deref()
+        .or(invoice.customer_email.as_deref())
+        .map(|c| format!(" ({c})"))
+        .unwrap_or_default()
+}
+
+/// `customer.subscription.created`
+fn subscription_created(dashboard: &Dashboard, object: Value) -> Result<String, Failure> {
+    let subscription: Subscription = parse_value(object)?;
+    let link = dashboard.link(&subscription.id, "subscriptions", &subscription.id);
+    let customer = dashboard.link(&subscription.customer, "customers", &subscription.customer);
+    let items = subscription_items_str(&subscription);
+    let prefix = dashboard.prefix();
+    Ok(format!(
+        "{prefix} :tada: Subscription {link} was created for {customer} ({status})\n{items}",
+        status = subscription.status,
+    ))
+}
+
+/// `customer.subscription.deleted`
+fn subscription_deleted(dashboard: &Dashboard, object: Value) -> Result<String, Failure> {
+    let subscription: Subscription = parse_value(object)?;
+    let link = dashboard.link(&subscription.id, "subscriptions", &subscription.id);
+    let customer = dashboard.link(&subscription.customer, "customers", &subscription.customer);
+    let reason = subscription
+        .cancellation_details
+        .as_ref()
+        .and_then(|d| d.reason.as_deref())
+        .map(|r| format!(" ({r})"))
+        .unwrap_or_default();
+    let items = subscription_items_str(&subscription);
+    let prefix = dashboard.prefix();
+    Ok(format!(
+        "{prefix} :wave: Subscription {link} for {customer} was canceled{reason}\n{items}"
+    ))
+}
+
+fn subscription_items_str(subscription: &Subscription) -> String {
+    subscription
+        .items
+        .data
+        .iter()
+        .map(|SubscriptionItem { price, quantity }| {
+            let name = price.nickname.as_deref().unwrap_or("price");
+            let amount = price
+                .unit_amount
+                .map(|a| amount_str(a, &price.currency))
+                .unwrap_or_default();
+            let interval = price
+                .recurring
+                .as_ref()
+                .map(|r| format!(" / {}", r.interval))
+                .unwrap_or_default();
+            let quantity = quantity
+                .filter(|q| *q > 1)
+                .map(|q| format!(" x {q}"))
+                .unwrap_or_default();
+            format!("- {name}: {amount}{interval}{quantity}\n")
+        })
+        .collect()
+}
+
+/// `charge.dispute.created`
+fn dispute_created(dashboard: &Dashboard, object: Value) -> Result<String, Failure> {
+    let dispute: Dispute = parse_value(object)?;
+    let link = dashboard.link(&dispute.id, "disputes", &dispute.id);
+    let amount = amount_str(dispute.amount, &dispute.currency);
+    let due_by = dispute
+        .evidence_details
+        .as_ref()
+        .and_then(|e| e.due_by)
+        .map(|d| {
+            let days = d.saturating_sub(now()) / (24 * 60 * 60);
+            format!("\nEvidence is due in {days} day(s)")
+        })
+        .unwrap_or_default();
+    let prefix = dashboard.prefix();
+    Ok(format!(
+        "{prefix} :warning: Dispute {link} was opened for {amount}: {reason}{due_by}\n",
+        reason = dispute.reason,
+    ))
+}
+
+/// `charge.refunded`
+fn charge_refunded(dashboard: &Dashboard, object: Value) -> Result<String, Failure> {
+    let charge: Charge = parse_value(object)?;
+    let link = dashboard.link(&charge.id, "payments", &charge.id);
+    let refunded = amount_str(charge.amount_refunded, &charge.currency);
+    let amount = amount_str(charge.amount, &charge.currency);
+    let email = charge
+        .billing_details
+        .as_ref()
+        .and_then(|b| b.email.as_deref())
+        .map(|e| format!(" ({e})"))
+        .unwrap_or_default();
+    let prefix = dashboard.prefix();
+    Ok(format!(
+        "{prefix} :leftwards_arrow_with_hook: {refunded} of {amount} was refunded for {link}{email}\n"
+    ))
+}
+
+/// <https://docs.stripe.com/currencies#zero-decimal>
+///
+/// 金額は通貨の最小単位で送られてくる
+fn amount_str(amount: i64, currency: &str) -> String {
+    const ZERO_DECIMAL: &[&str] = &[
+        "bif", "clp", "djf", "gnf", "jpy", "kmf", "krw", "mga", "pyg", "rwf", "ugx", "vnd", "vuv",
+        "xaf", "xof", "xpf",
+    ];
+    // <https://docs.stripe.com/currencies#three-decimal>
+    const THREE_DECIMAL: &[&str] = &["bhd", "jod", "kwd", "omr", "tnd"];
+    let code = currency.to_ascii_uppercase();
+    let currency = currency.to_ascii_lowercase();
+    if ZERO_DECIMAL.contains(&currency.as_str()) {
+        return format!("{amount} {code}");
+    }
+    let sign = if amount < 0 { "-" } else { "" };
+    let amount = amount.unsigned_abs();
+    if THREE_DECIMAL.contains(&currency.as_str()) {
+        return format!("{sign}{}.{:03} {code}", amount / 1000, amount % 1000);
+    }
+    format!("{sign}{}.{:02} {code}", amount / 100, amount % 100)
+}
+
+#[cfg(test)]
+mod tests {
+    use hmac::{Hmac, Mac};
+    use sha2::Sha256;
+
+    use super::*;
+
+    const SECRET: &str = "whsec_test";
+    const PAYLOAD: &str = r#"{"type":"invoice.paid"}"#;
+    const NOW: u64 = 1_700_000_000;
+
+    fn sign(secret: &str, timestamp: u64, payload: &str) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(format!("{timestamp}.{payload}").as_bytes());
+        hex::encode(mac.finalize().into_bytes())
+    }
+
+    fn verify(header: &str, now: u64) -> Result<(), Failure> {
+        verify_signature(SECRET, PAYLOAD, header.as_bytes(), now)
+    }
+
+    #[test]
+    fn accepts_valid_signature() {
+        let header = format!("t={NOW},v1={}", sign(SECRET, NOW, PAYLOAD));
+        assert!(verify(&header, NOW).is_ok());
+    }
+
+    #[test]
+    fn accepts_any_signature_during_rotation() {
+        let old = sign("whsec_old", NOW, PAYLOAD);
+        let new = sign(SECRET, NOW, PAYLOAD);
+        let header = format!("t={NOW},v1={old},v1={new},v0=ignored");
+        assert!(verify(&header, NOW).is_ok());
+    }
+
+    #[test]
+    fn rejects_wrong_secret() {
+        let header = format!("t={NOW},v1={}", sign("whsec_other", NOW, PAYLOAD));
+        assert!(matches!(verify(&header, NOW), Err(Failure::Reject(_))));
+    }
+
+    #[test]
+    fn rejects_timestamp_outside_tolerance() {
+        let signed_at = NOW - TOLERANCE_SECS - 1;
+        let header = format!("t={signed_at},v1={}", sign(SECRET, signed_at, PAYLOAD));
+        assert!(matches!(verify(&header, NOW), Err(Failure::Reject(_))));
+        let signed_at = NOW - TOLERANCE_SECS;
+        let header = format!("t={signed_at},v1={}", sign(SECRET, signed_at, PAYLOAD));
+        assert!(verify(&header, NOW).is_ok());
+    }
+
+    #[test]
+    fn rejects_malformed_header() {
+        let signature = sign(SECRET, NOW, PAYLOAD);
+        assert!(matches!(
+            verify(&format!("v1={signature}"), NOW),
+            Err(Failure::Reject(_))
+        ));
+        assert!(matches!(
+            verify(&format!("t=now,v1={signature}"), NOW),
+            Err(Failure::Reject(_))
+        ));
+        assert!(matches!(
+            verify(&format!("t={NOW}"), NOW),
+            Err(Failure::Reject(_))
+        ));
+    }
+
+    #[test]
+    fn formats_amounts_by_currency_exponent() {
+        assert_eq!(amount_str(1234, "usd"), "12.34 USD");
+        assert_eq!(amount_str(-5, "eur"), "-0.05 EUR");
+        assert_eq!(amount_str(1234, "jpy"), "1234 JPY");
+        assert_eq!(amount_str(1234, "KWD"), "1.234 KWD");
+        assert_eq!(amount_str(-50, "bhd"), "-0.050 BHD");
+    }
+}
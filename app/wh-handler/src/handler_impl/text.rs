@@ -0,0 +1,76 @@
+use http::HeaderMap;
+use serde::Deserialize;
+
+use domain::{Event, Failure, Webhook};
+
+use super::Handled;
+use super::utils::{require_secret, truncate, verify_authorization};
+use crate::WebhookHandlerImpl;
+
+/// traQのメッセージの最大文字数
+const MAX_MESSAGE_LENGTH: usize = 10000;
+
+impl WebhookHandlerImpl {
+    pub(crate) fn handle_text(
+        &self,
+        webhook: &Webhook,
+        headers: HeaderMap,
+        payload: &str,
+    ) -> Result<Option<Handled>, Failure> {
+        let Some(message) = handle(webhook.secret(), headers, payload)? else {
+            return Ok(None);
+        };
+        let event_type = "text".to_string();
+        let kind = event_type.clone().into();
+        let event = Event {
+            destination: webhook.destination(),
+            kind,
+            body: message.into(),
+            collapse: false,
+            deliveries: vec![],
+        };
+        Ok(Some(Handled { event, event_type }))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Form {
+    message: String,
+    title: Option<String>,
+}
+
+#[tracing::instrument(target = "wh_handler::text::handle", skip_all)]
+fn handle(
+    secret: Option<&str>,
+    headers: HeaderMap,
+    payload: &str,
+) -> Result<Option<String>, Failure> {
+    // クエリの`token`はrouterで`Authorization`ヘッダーに移されている
+    let secret = require_secret(secret)?;
+    verify_authorization(&headers, secret)?;
+    let content_type = headers
+        .get(http::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    // `curl -d "..."`はContent-Typeを`application/x-www-form-urlencoded`にするので、
+    // `message`フィールドが無ければ本文をそのまま使う
+    let form = content_type
+        .starts_with("application/x-www-form-urlencoded")
+        .then(|| serde_urlencoded::from_str::<Form>(payload).ok())
+        .flatten();
+    let message = match form {
+        Some(Form {
+            message,
+            title: Some(title),
+        }) => format!("### {title}\n{message}"),
+        Some(Form {
+            message,
+            title: None,
+        }) => message,
+        None => payload.to_string(),
+    };
+    if message.trim().is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(truncate(message, MAX_MESSAGE_LENGTH)))
+}
@@ -0,0 +1,281 @@
+use http::HeaderMap;
+use serde::Deserialize;
+use serde_json::Value;
+
+use domain::{Event, Failure, Webhook};
+
+use super::Handled;
+use super::utils::{duration_str, parse_payload};
+use crate::WebhookHandlerImpl;
+
+impl WebhookHandlerImpl {
+    pub(crate) fn handle_uptime(
+        &self,
+        webhook: &Webhook,
+        headers: HeaderMap,
+        payload: &str,
+    ) -> Result<Option<Handled>, Failure> {
+        let Some((monitor, event_type, message)) = handle(headers, payload)? else {
+            return Ok(None);
+        };
+        // 同じモニターの状態変化はnotifierで最新のものだけにまとめられる
+        let kind = format!("uptime:{monitor}").into();
+        let event = Event {
+            destination: webhook.destination(),
+            kind,
+            body: message.into(),
+            collapse: true,
+            deliveries: vec![],
+        };
+        Ok(Some(Handled { event, event_type }))
+    }
+}
+
+/// 送信元ごとに形式が異なるので、フィールドの有無で判別する
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum Payload {
+    Mackerel(MackerelPayload),
+    UptimeRobot(UptimeRobotPayload),
+    UptimeKuma(UptimeKumaPayload),
+}
+
+/// <https://github.com/louislam/uptime-kuma/blob/master/server/notification-providers/webhook.js>
+#[derive(Debug, Clone, Deserialize)]
+struct UptimeKumaPayload {
+    heartbeat: Option<KumaHeartbeat>,
+    monitor: Option<KumaMonitor>,
+    msg: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct KumaHeartbeat {
+    status: u8,
+    #[serde(default)]
+    msg: String,
+    time: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct KumaMonitor {
+    name: String,
+    url: Option<String>,
+}
+
+/// <https://uptimerobot.com/api/> の Alert Contacts > Webhook
+///
+/// 値はすべて文字列で送られることもあるので`Value`で受ける
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UptimeRobotPayload {
+    monitor_friendly_name: String,
+    #[serde(rename = "monitorURL")]
+    monitor_url: Option<String>,
+    alert_type: Value,
+    alert_details: Option<String>,
+    alert_duration: Option<Value>,
+}
+
+/// <https://mackerel.io/docs/entry/howto/alerts/webhook>
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MackerelPayload {
+    org_name: String,
+    event: String,
+    host: Option<MackerelHost>,
+    alert: Option<MackerelAlert>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct MackerelHost {
+    name: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MackerelAlert {
+    status: String,
+    url: String,
+    monitor_name: Option<String>,
+    #[serde(default)]
+    is_open: bool,
+    opened_at: Option<u64>,
+    closed_at: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Up,
+    Down,
+    Pending,
+    Maintenance,
+}
+
+impl State {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Up => "up",
+            Self::Down => "down",
+            Self::Pending => "pending",
+            Self::Maintenance => "maintenance",
+        }
+    }
+
+    fn icon(self) -> &'static str {
+        match self {
+            Self::Up => ":large_green_circle: UP",
+            Self::Down => ":red_circle: DOWN",
+            Self::Pending => ":large_yellow_circle: PENDING",
+            Self::Maintenance => ":large_blue_circle: MAINTENANCE",
+        }
+    }
+}
+
+/// モニターの識別子・イベントの種類 (`up`, `down`, ...)・メッセージを返す
+#[tracing::instrument(target = "wh_handler::uptime::handle", skip_all)]
+fn handle(headers: HeaderMap, payload: &str) -> Result<Option<(String, String, String)>, Failure> {
+    let is_form = headers
+        .get(http::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/x-www-form-urlencoded"));
+    // UptimeRobotはPOST valueをJSONにしない場合フォームで送ってくる
+    let payload = if is_form {
+        serde_urlencoded::from_str(payload)
+            .map(Payload::UptimeRobot)
+            .map_err(|e| {
+                let message = format!("Received unexpected payload: {e}");
+                Failure::reject_bad_request(message)
+            })?
+    } else {
+        parse_payload(payload)?
+    };
+    let message = match payload {
+        Payload::UptimeKuma(payload) => Some(uptime_kuma(payload)),
+        Payload::UptimeRobot(payload) => uptime_robot(payload),
+        Payload::Mackerel(payload) => mackerel(payload),
+    };
+    Ok(message.map(|(monitor, state, message)| (monitor, state.to_string(), message)))
+}
+
+fn uptime_kuma(payload: UptimeKumaPayload) -> (String, &'static str, String) {
+    let UptimeKumaPayload {
+        heartbeat,
+        monitor,
+        msg,
+    } = payload;
+    // テスト通知ではheartbeatとmonitorがnullになる
+    let (Some(heartbeat), Some(monitor)) = (heartbeat, monitor) else {
+        return ("kuma".to_string(), "test", format!("[Uptime Kuma] {msg}\n"));
+    };
+    let state = match heartbeat.status {
+        0 => State::Down,
+        1 => State::Up,
+        3 => State::Maintenance,
+        _ => State::Pending,
+    };
+    let url = monitor.url.as_deref().filter(|u| u.starts_with("http"));
+    let since = heartbeat
+        .time
+        .map(|t| format!(" (since {t})"))
+        .unwrap_or_default();
+    let message = format!(
+        "[Uptime Kuma] {icon} {monitor}{since}\n{detail}\n",
+        icon = state.icon(),
+        monitor = monitor_str(&monitor.name, url),
+        detail = heartbeat.msg,
+    );
+    (format!("kuma:{}", monitor.name), state.as_str(), message)
+}
+
+fn uptime_robot(payload: UptimeRobotPayload) -> Option<(String, &'static str, String)> {
+    let UptimeRobotPayload {
+        monitor_friendly_name,
+        monitor_url,
+        alert_type,
+        alert_details,
+        alert_duration,
+    } = payload;
+    // 1: down, 2: up, 3: SSL証明書の期限切れ
+    let state = match number(&alert_type)? {
+        1 => State::Down,
+        2 => State::Up,
+        _ => return None,
+    };
+    // upの通知ではdownしていた時間が入る
+    let duration = alert_duration
+        .as_ref()
+        .and_then(number)
+        .filter(|_| state == State::Up)
+        .map(|d| format!(" (down for {})", duration_str(d)))
+        .unwrap_or_default();
+    let url = monitor_url.as_deref().filter(|u| u.starts_with("http"));
+    let message = format!(
+        "[UptimeRobot] {icon} {monitor}{duration}\n{detail}\n",
+        icon = state.icon(),
+        monitor = monitor_str(&monitor_friendly_name, url),
+        detail = alert_details.unwrap_or_default(),
+    );
+    Some((
+        format!("uptimerobot:{monitor_friendly_name}"),
+        state.as_str(),
+        message,
+    ))
+}
+
+/// `alert`イベント以外 (ホストの登録など) は無視する
+fn mackerel(payload: MackerelPayload) -> Option<(String, &'static str, String)> {
+    let MackerelPayload {
+        org_name,
+        event,
+        host,
+        alert,
+    } = payload;
+    if event != "alert" {
+        return None;
+    }
+    let MackerelAlert {
+        status,
+        url,
+        monitor_name,
+        is_open,
+        opened_at,
+        closed_at,
+    } = alert?;
+    let state = match (is_open, status.as_str()) {
+        (false, _) | (true, "ok") => State::Up,
+        (true, "warning" | "unknown") => State::Pending,
+        (true, _) => State::Down,
+    };
+    let monitor = monitor_name.unwrap_or_else(|| "alert".to_string());
+    let host = host.map(|h| format!(" on {}", h.name)).unwrap_or_default();
+    let duration = match (opened_at, closed_at) {
+        (Some(opened_at), Some(closed_at)) if !is_open => {
+            format!(
+                " (lasted {})",
+                duration_str(closed_at.saturating_sub(opened_at))
+            )
+        }
+        _ => String::new(),
+    };
+    let message = format!(
+        "[{org_name}] {icon} `{status}` [{monitor}]({url}){host}{duration}\n",
+        icon = state.icon(),
+    );
+    Some((format!("mackerel:{monitor}{host}"), state.as_str(), message))
+}
+
+fn monitor_str(name: &str, url: Option<&str>) -> String {
+    match url {
+        Some(url) => format!("**{name}** <{url}>"),
+        None => format!("**{name}**"),
+    }
+}
+
+/// 数値と数字の文字列の両方を受け付ける
+fn number(value: &Value) -> Option<u64> {
+    match value {
+        Value::Number(n) => n.as_u64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
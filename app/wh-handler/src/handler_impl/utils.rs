@@ -1,5 +1,10 @@
+use hmac::{Hmac, Mac};
 use http::HeaderMap;
+use serde::de::DeserializeOwned;
 use serde_json::{Value, value::Index};
+use sha1::Sha1;
+use sha2::Sha256;
+use subtle::ConstantTimeEq;
 
 use domain::Failure;
 
@@ -13,6 +18,137 @@ pub(crate) fn extract_header_value<'a>(
         .ok_or_err()
 }
 
+/// JSONのペイロードを読み取る。形式が合わなければ不正なリクエストとして拒否する
+pub(crate) fn parse_payload<T: DeserializeOwned>(payload: &str) -> Result<T, Failure> {
+    serde_json::from_str(payload).map_err(|e| {
+        let message = format!("Received unexpected payload: {e}");
+        Failure::reject_bad_request(message)
+    })
+}
+
+/// ペイロードの一部 (`data`など) を読み取る。形式が合わなければ不正なリクエストとして拒否する
+pub(crate) fn parse_value<T: DeserializeOwned>(value: Value) -> Result<T, Failure> {
+    serde_json::from_value(value).map_err(|e| {
+        let message = format!("Received unexpected payload: {e}");
+        Failure::reject_bad_request(message)
+    })
+}
+
+/// `name`ヘッダーの値。UTF-8でなければ`None`
+pub(crate) fn header_str(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers.get(name)?.to_str().ok().map(ToString::to_string)
+}
+
+/// イベントの種類を表すヘッダーの値。ペイロードを読む前に分かるものだけで、配信記録に使う
+pub(crate) fn event_type(headers: &HeaderMap) -> Option<String> {
+    const EVENT_HEADERS: [&str; 3] = ["X-GitHub-Event", "X-Gitea-Event", "Sentry-Hook-Resource"];
+    EVENT_HEADERS
+        .iter()
+        .find_map(|name| header_str(headers, name))
+}
+
+/// 送信元が配信ごとに付けるIDを表すヘッダーの値
+pub(crate) fn delivery_id(headers: &HeaderMap) -> Option<String> {
+    const DELIVERY_HEADERS: [&str; 4] = [
+        "X-GitHub-Delivery",
+        "X-Gitea-Delivery",
+        "Linear-Delivery",
+        "Request-ID",
+    ];
+    DELIVERY_HEADERS
+        .iter()
+        .find_map(|name| header_str(headers, name))
+}
+
+/// `signature`はHMAC-SHA256のhex表現
+pub(crate) fn verify_hmac_sha256(
+    secret: &str,
+    payload: &[u8],
+    signature: &[u8],
+) -> Result<(), Failure> {
+    let signature = hex::decode(signature)
+        .map_err(|_| Failure::reject_unauthorized("Received malformed signature"))?;
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .map_err(|e| anyhow::anyhow!("Failed to initialize HMAC: {e}"))?;
+    mac.update(payload);
+    mac.verify_slice(&signature)
+        .map_err(|_| Failure::reject_unauthorized("Signature mismatch"))
+}
+
+/// `signature`はHMAC-SHA1のhex表現
+pub(crate) fn verify_hmac_sha1(
+    secret: &str,
+    payload: &[u8],
+    signature: &[u8],
+) -> Result<(), Failure> {
+    let signature = hex::decode(signature)
+        .map_err(|_| Failure::reject_unauthorized("Received malformed signature"))?;
+    let mut mac = Hmac::<Sha1>::new_from_slice(secret.as_bytes())
+        .map_err(|e| anyhow::anyhow!("Failed to initialize HMAC: {e}"))?;
+    mac.update(payload);
+    mac.verify_slice(&signature)
+        .map_err(|_| Failure::reject_unauthorized("Signature mismatch"))
+}
+
+/// `Authorization: <scheme> <credentials>`の`credentials`を`secret`と比較する
+pub(crate) fn verify_authorization(headers: &HeaderMap, secret: &str) -> Result<(), Failure> {
+    let value = headers
+        .get(http::header::AUTHORIZATION)
+        .ok_or_else(|| Failure::reject_unauthorized("Missing Authorization header"))?;
+    let credentials = value
+        .to_str()
+        .ok()
+        .and_then(|v| v.split_once(' '))
+        .map(|(_, c)| c.trim());
+    // 比較にかかる時間から`secret`を推測されないよう定数時間で比較する
+    let matched = credentials.is_some_and(|c| bool::from(c.as_bytes().ct_eq(secret.as_bytes())));
+    if matched {
+        Ok(())
+    } else {
+        Err(Failure::reject_unauthorized("Authorization mismatch"))
+    }
+}
+
+/// 署名の検証が必須のWebhookで、シークレットが設定されていなければ拒否する
+pub(crate) fn require_secret(secret: Option<&str>) -> Result<&str, Failure> {
+    secret.ok_or_else(|| Failure::reject_unauthorized("Webhook secret is not configured"))
+}
+
+/// `0xRRGGBB`の色に近い丸の絵文字
+pub(crate) fn color_emoji(rgb: u32) -> &'static str {
+    let [_, r, g, b] = rgb.to_be_bytes();
+    match (r >= 0x80, g >= 0x80, b >= 0x80) {
+        (true, true, false) => ":large_yellow_circle:",
+        (true, false, _) => ":red_circle:",
+        (false, true, false) => ":large_green_circle:",
+        (false, _, true) => ":large_blue_circle:",
+        _ => ":white_circle:",
+    }
+}
+
+/// 秒数を`1h 2m 3s`のように表示する
+pub(crate) fn duration_str(secs: u64) -> String {
+    let (h, m, s) = (secs / 3600, secs / 60 % 60, secs % 60);
+    match (h, m) {
+        (0, 0) => format!("{s}s"),
+        (0, _) => format!("{m}m {s}s"),
+        _ => format!("{h}h {m}m {s}s"),
+    }
+}
+
+/// `max_length`文字を超える場合は末尾を切り詰めて`(truncated)`を付ける
+pub(crate) fn truncate(mut message: String, max_length: usize) -> String {
+    const SUFFIX: &str = "\n(truncated)";
+    if message.chars().count() <= max_length {
+        return message;
+    }
+    let limit = max_length.saturating_sub(SUFFIX.chars().count());
+    let (end, _) = message.char_indices().nth(limit).unwrap_or_default();
+    message.truncate(end);
+    message += SUFFIX;
+    message
+}
+
 pub(crate) trait OptionExt {
     type Inner;
     fn ok_or_err(self) -> Result<Self::Inner, Failure>;
@@ -78,3 +214,19 @@ impl ValueExt for Value {
             .ok_or_else(|| Failure::reject_bad_request("Received unexpected payload"))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use domain::error::RejectKind;
+
+    use super::*;
+
+    #[test]
+    fn require_secret_rejects_missing_secret() {
+        assert_eq!(require_secret(Some("s3cret")).unwrap(), "s3cret");
+        let Err(Failure::Reject(r)) = require_secret(None) else {
+            panic!("expected rejection");
+        };
+        assert_eq!(r.kind(), RejectKind::Unauthorized);
+    }
+}
@@ -0,0 +1,181 @@
+use std::collections::BTreeMap;
+
+use http::HeaderMap;
+use serde::Deserialize;
+
+use domain::{Event, Failure, Webhook};
+
+use super::Handled;
+use super::utils::{extract_header_value, parse_payload, require_secret, verify_hmac_sha1};
+use crate::WebhookHandlerImpl;
+
+impl WebhookHandlerImpl {
+    pub(crate) fn handle_vercel(
+        &self,
+        webhook: &Webhook,
+        headers: HeaderMap,
+        payload: &str,
+    ) -> Result<Option<Handled>, Failure> {
+        let Some((event_type, message)) = handle(webhook.secret(), headers, payload)? else {
+            return Ok(None);
+        };
+        let kind = "vercel".to_string().into();
+        let event = Event {
+            destination: webhook.destination(),
+            kind,
+            body: message.into(),
+            collapse: false,
+            deliveries: vec![],
+        };
+        Ok(Some(Handled { event, event_type }))
+    }
+}
+
+/// <https://vercel.com/docs/webhooks/webhooks-api>
+#[derive(Debug, Clone, Deserialize)]
+struct Payload {
+    r#type: String,
+    payload: DeploymentPayload,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct DeploymentPayload {
+    deployment: Deployment,
+    links: Option<Links>,
+    /// previewの場合は`null`
+    target: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Deployment {
+    name: String,
+    url: String,
+    /// `githubCommitSha`, `gitlabCommitSha`などGitプロバイダごとに異なるキーが入る
+    #[serde(default)]
+    meta: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Links {
+    deployment: Option<String>,
+    project: Option<String>,
+}
+
+/// イベントの種類とメッセージを返す
+#[tracing::instrument(target = "wh_handler::vercel::handle", skip_all)]
+fn handle(
+    secret: Option<&str>,
+    headers: HeaderMap,
+    payload: &str,
+) -> Result<Option<(String, String)>, Failure> {
+    // https://vercel.com/docs/webhooks/webhooks-api#securing-webhooks
+    let secret = require_secret(secret)?;
+    let signature = extract_header_value(&headers, "x-vercel-signature")?;
+    verify_hmac_sha1(secret, payload.as_bytes(), signature)?;
+    let payload: Payload = parse_payload(payload)?;
+    tracing::info!("Vercel: {}", payload.r#type);
+    let action = match payload.r#type.as_str() {
+        "deployment.created" => ":rocket: Deployment started",
+        "deployment.succeeded" => ":white_check_mark: Deployment succeeded",
+        "deployment.error" => ":x: Deployment failed",
+        "deployment.canceled" => ":no_entry_sign: Deployment canceled",
+        // deployment.promoted, project.created, ...
+        _ => return Ok(None),
+    };
+    let message = deployment_str(action, &payload.payload);
+    Ok(Some((payload.r#type, message)))
+}
+
+fn deployment_str(action: &str, payload: &DeploymentPayload) -> String {
+    let DeploymentPayload {
+        deployment: Deployment { name, url, meta },
+        links,
+        target,
+    } = payload;
+    let links = links.as_ref();
+    let project = match links.and_then(|l| l.project.as_deref()) {
+        Some(project_url) => format!("[{name}]({project_url})"),
+        None => name.clone(),
+    };
+    let target = target.as_deref().unwrap_or("preview");
+    let git = |key: &str| {
+        meta.iter()
+            .find(|(k, v)| k.ends_with(key) && !v.is_empty())
+            .map(|(_, v)| v.as_str())
+    };
+    let branch = git("CommitRef")
+        .map(|r| format!(":{r}"))
+        .unwrap_or_default();
+    let commit = git("CommitSha").map(|sha| {
+        let sha = sha.get(..7).unwrap_or(sha);
+        let message = git("CommitMessage")
+            .and_then(|m| m.lines().next())
+            .unwrap_or_default();
+        let author = git("CommitAuthorName")
+            .or_else(|| git("CommitAuthorLogin"))
+            .map(|a| format!(" by {a}"))
+            .unwrap_or_default();
+        format!("`{sha}` {message}{author}")
+    });
+    let inspect = links
+        .and_then(|l| l.deployment.as_deref())
+        .map(|url| format!(" ([inspect]({url}))"))
+        .unwrap_or_default();
+    let mut message = format!("[{project}{branch}] {action} to {target}\n");
+    if let Some(commit) = commit {
+        message += &format!("{commit}\n");
+    }
+    message += &format!("https://{url}{inspect}\n");
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use hmac::{Hmac, Mac};
+    use sha1::Sha1;
+
+    use super::*;
+
+    const SECRET: &str = "vercel-secret";
+    const PAYLOAD: &str = r#"{
+        "type": "deployment.succeeded",
+        "payload": {
+            "deployment": {
+                "name": "app",
+                "url": "app-abc.vercel.app",
+                "meta": { "githubCommitRef": "main", "githubCommitSha": "0123456789abcdef" }
+            },
+            "target": "production"
+        }
+    }"#;
+
+    fn headers(secret: &str, payload: &str) -> HeaderMap {
+        let mut mac = Hmac::<Sha1>::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(payload.as_bytes());
+        let signature = hex::encode(mac.finalize().into_bytes());
+        let mut headers = HeaderMap::new();
+        headers.insert("x-vercel-signature", signature.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn accepts_valid_signature() {
+        let res = handle(Some(SECRET), headers(SECRET, PAYLOAD), PAYLOAD).unwrap();
+        let (event_type, message) = res.unwrap();
+        assert_eq!(event_type, "deployment.succeeded");
+        assert!(message.contains("`0123456`"), "{message}");
+    }
+
+    #[test]
+    fn rejects_wrong_secret() {
+        let res = handle(Some(SECRET), headers("other", PAYLOAD), PAYLOAD);
+        assert!(matches!(res, Err(Failure::Reject(_))));
+    }
+
+    #[test]
+    fn rejects_tampered_payload() {
+        let tampered = PAYLOAD.replace("production", "preview");
+        let res = handle(Some(SECRET), headers(SECRET, PAYLOAD), &tampered);
+        assert!(matches!(res, Err(Failure::Reject(_))));
+    }
+}
@@ -1,6 +1,9 @@
 pub mod notifier;
+pub mod pruner;
 pub mod subscriber;
 
+use std::time::Duration;
+
 use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
 
 use domain::Event;
@@ -13,6 +16,13 @@ pub struct Subscriber(pub(crate) UnboundedSender<Event>);
 #[derive(Debug)]
 pub struct Notifier(pub(crate) UnboundedReceiver<Event>);
 
+/// 配信記録を保存期間が過ぎたら削除する
+#[must_use]
+#[derive(Debug, Clone)]
+pub struct Pruner {
+    pub(crate) retention: Duration,
+}
+
 pub fn channel() -> (Subscriber, Notifier) {
     let (tx, rx) = mpsc::unbounded_channel();
     (Subscriber(tx), Notifier(rx))
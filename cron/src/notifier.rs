@@ -4,10 +4,19 @@ use std::time::Duration;
 use tokio::time::interval;
 use tokio_stream::{Stream, StreamExt, wrappers::UnboundedReceiverStream};
 
-use domain::{Event, Infra, TraqClient};
+use domain::{
+    DeliveryStatus, Destination, Event, EventSink, Failure, Infra, Repository, Sink, SinkId,
+    TraqClient,
+};
 
 use crate::Notifier;
 
+/// 転送先への送信を試みる回数
+const SINK_ATTEMPTS: u32 = 3;
+
+/// 転送先への再送までの待ち時間。再送のたびに倍にする
+const SINK_RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
 async fn collect_event_stream<S>(mut stream: S) -> Vec<Event>
 where
     S: Stream<Item = Event> + Send + Unpin,
@@ -25,18 +34,78 @@ where
     events
 }
 
-async fn send_events(infra: &impl Infra, events: &[Event]) {
+async fn send_events<I: Infra>(infra: &Arc<I>, events: &[Event]) {
     if !events.is_empty() {
         tracing::info!("sending {} events...", events.len());
     }
     for event in events {
-        tracing::info!(event_kind = event.kind(), channel_id = %event.channel_id());
+        tracing::info!(event_kind = event.kind(), destination = %event.destination());
+        let client = infra.traq_client();
+        let res = match event.destination() {
+            Destination::Channel(channel_id) => {
+                client.send_message(channel_id, &event.body(), false).await
+            }
+            Destination::DirectMessage(user_id) => {
+                client
+                    .send_direct_message(user_id, &event.body(), false)
+                    .await
+            }
+            Destination::Sink(sink_id) => {
+                spawn_send_to_sink(infra, *sink_id, event.clone());
+                continue;
+            }
+        };
+        if let Err(e) = res {
+            tracing::error!(error = ?e);
+            record_failure(infra, event, &e).await;
+        }
+    }
+}
+
+/// 元になった配信の記録を`Failed`にして、`webhook deliveries`で分かるようにする
+async fn record_failure<I: Infra>(infra: &Arc<I>, event: &Event, failure: &Failure) {
+    let error = failure.to_string();
+    for delivery_id in &event.deliveries {
         let res = infra
-            .traq_client()
-            .send_message(event.channel_id(), &event.body(), false)
+            .repo()
+            .update_delivery_status(delivery_id, DeliveryStatus::Failed, Some(&error))
             .await;
         if let Err(e) = res {
-            tracing::error!(error = ?e);
+            tracing::warn!(%delivery_id, error = ?e, "failed to record delivery failure");
+        }
+    }
+}
+
+/// 転送先への送信は再送で数秒かかることがあるので、次のイベントの受信を止めないよう別のタスクで行う
+fn spawn_send_to_sink<I: Infra>(infra: &Arc<I>, sink_id: SinkId, event: Event) {
+    let infra = Arc::clone(infra);
+    tokio::spawn(async move {
+        // URLはイベントに含めず、送信の直前に引く
+        match infra.repo().find_sink(&sink_id).await {
+            Ok(sink) => send_to_sink(infra.event_sink(), &sink, &event).await,
+            Err(e) => tracing::error!(%sink_id, error = ?e, "failed to find sink"),
+        }
+    });
+}
+
+async fn send_to_sink(event_sink: &impl EventSink, sink: &Sink, event: &Event) {
+    let mut retry_interval = SINK_RETRY_INTERVAL;
+    for attempt in 1..=SINK_ATTEMPTS {
+        match event_sink.send(sink, event).await {
+            Ok(()) => return,
+            // 転送先に拒否された場合は再送しても変わらない
+            Err(Failure::Reject(r)) => {
+                tracing::error!(sink = %sink.kind, "event rejected by sink: {r}");
+                return;
+            }
+            Err(Failure::Error(e)) if attempt < SINK_ATTEMPTS => {
+                tracing::warn!(sink = %sink.kind, attempt, error = ?e, "retrying");
+                tokio::time::sleep(retry_interval).await;
+                retry_interval *= 2;
+            }
+            Err(Failure::Error(e)) => {
+                tracing::error!(sink = %sink.kind, error = ?e, "failed to send event to sink");
+            }
         }
     }
 }
@@ -52,7 +121,112 @@ impl Notifier {
             tracing::trace!("tick");
             let event_stream = (&mut recv_stream).map_while(Result::ok);
             let events = collect_event_stream(event_stream).await;
-            send_events(&*infra, &events).await;
+            send_events(&infra, &events).await;
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use tokio::time::Instant;
+    use uuid::Uuid;
+
+    use domain::{DeliveryId, SinkKind};
+
+    use super::*;
+
+    /// `results`を先頭から順に返す転送先
+    struct FakeSink {
+        results: Mutex<Vec<Result<(), Failure>>>,
+        calls: AtomicU32,
+    }
+
+    impl FakeSink {
+        fn new(results: Vec<Result<(), Failure>>) -> Self {
+            Self {
+                results: Mutex::new(results),
+                calls: AtomicU32::new(0),
+            }
+        }
+
+        fn calls(&self) -> u32 {
+            self.calls.load(Ordering::SeqCst)
+        }
+    }
+
+    impl EventSink for FakeSink {
+        async fn send(&self, _sink: &Sink, _event: &Event) -> Result<(), Failure> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            self.results.lock().unwrap().remove(0)
+        }
+    }
+
+    fn sink() -> Sink {
+        Sink {
+            id: SinkId(Uuid::nil()),
+            kind: SinkKind::Http,
+            url: "http://localhost/hook".to_string().into(),
+        }
+    }
+
+    fn event() -> Event {
+        Event {
+            destination: Destination::Sink(SinkId(Uuid::nil())),
+            kind: "test".to_string().into(),
+            body: "hello".to_string().into(),
+            collapse: false,
+            deliveries: vec![],
+        }
+    }
+
+    fn error() -> Result<(), Failure> {
+        Err(anyhow::anyhow!("Sink responded with 503 Service Unavailable").into())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn retries_errors_with_backoff() {
+        let fake = FakeSink::new(vec![error(), error(), Ok(())]);
+        let started = Instant::now();
+        send_to_sink(&fake, &sink(), &event()).await;
+        assert_eq!(fake.calls(), 3);
+        assert_eq!(started.elapsed(), SINK_RETRY_INTERVAL * 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn gives_up_after_attempts() {
+        let fake = FakeSink::new(vec![error(), error(), error(), Ok(())]);
+        send_to_sink(&fake, &sink(), &event()).await;
+        assert_eq!(fake.calls(), SINK_ATTEMPTS);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn does_not_retry_rejections() {
+        let rejected = Err(Failure::reject_bad_request(
+            "Sink responded with 404 Not Found",
+        ));
+        let fake = FakeSink::new(vec![rejected, Ok(())]);
+        send_to_sink(&fake, &sink(), &event()).await;
+        assert_eq!(fake.calls(), 1);
+    }
+
+    #[tokio::test]
+    async fn merged_events_keep_all_deliveries() {
+        let delivered = |n| Event {
+            deliveries: vec![DeliveryId(Uuid::from_u128(n))],
+            ..event()
+        };
+        let stream = tokio_stream::iter([delivered(1), delivered(2)]);
+        let events = collect_event_stream(stream).await;
+        assert_eq!(events.len(), 1);
+        assert_eq!(
+            events[0].deliveries,
+            [
+                DeliveryId(Uuid::from_u128(1)),
+                DeliveryId(Uuid::from_u128(2))
+            ]
+        );
+    }
+}
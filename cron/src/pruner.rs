@@ -0,0 +1,28 @@
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use tokio::time::interval;
+
+use domain::{Infra, Repository};
+
+use crate::Pruner;
+
+impl Pruner {
+    /// `retention`より古い配信記録を削除する
+    pub fn new(retention: Duration) -> Self {
+        Self { retention }
+    }
+
+    pub async fn run(self, infra: Arc<impl Infra>, period: Duration) {
+        let mut interval = interval(period);
+        loop {
+            interval.tick().await;
+            let Some(before) = SystemTime::now().checked_sub(self.retention) else {
+                continue;
+            };
+            if let Err(e) = infra.repo().remove_deliveries_before(before).await {
+                tracing::warn!("failed to prune deliveries: {e}");
+            }
+        }
+    }
+}
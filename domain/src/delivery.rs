@@ -0,0 +1,80 @@
+use std::fmt::{self, Display};
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
+
+use uuid::Uuid;
+
+use crate::{Delivery, DeliveryStatus, WebhookId};
+
+impl Delivery {
+    /// 今受け取った`payload`の記録。処理結果は`Accepted`にしておく
+    pub fn new(webhook_id: WebhookId, provider: impl Into<String>, payload: &str) -> Self {
+        Self {
+            id: Uuid::now_v7().into(),
+            webhook_id,
+            provider: provider.into(),
+            event: None,
+            remote_id: None,
+            status: DeliveryStatus::Accepted,
+            error: None,
+            payload: payload.to_string(),
+        }
+    }
+
+    pub fn with_event(self, event: Option<String>) -> Self {
+        Self { event, ..self }
+    }
+
+    pub fn with_remote_id(self, remote_id: Option<String>) -> Self {
+        Self { remote_id, ..self }
+    }
+
+    pub fn with_status(self, status: DeliveryStatus, error: Option<String>) -> Self {
+        Self {
+            status,
+            error,
+            ..self
+        }
+    }
+
+    /// `id`に含まれる受け取った時刻
+    #[must_use]
+    pub fn received_at(&self) -> Option<SystemTime> {
+        let (secs, nanos) = self.id.0.get_timestamp()?.to_unix();
+        Some(SystemTime::UNIX_EPOCH + Duration::new(secs, nanos))
+    }
+}
+
+impl DeliveryStatus {
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Accepted => "accepted",
+            Self::Filtered => "filtered",
+            Self::Rejected => "rejected",
+            Self::Error => "error",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+impl FromStr for DeliveryStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "accepted" => Ok(Self::Accepted),
+            "filtered" => Ok(Self::Filtered),
+            "rejected" => Ok(Self::Rejected),
+            "error" => Ok(Self::Error),
+            "failed" => Ok(Self::Failed),
+            _ => Err(format!("unexpected delivery status: {s}")),
+        }
+    }
+}
+
+impl Display for DeliveryStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
@@ -1,10 +1,15 @@
 use std::borrow::Cow;
 
-use crate::{ChannelId, Event};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{Destination, Event, SinkKind};
+
+const FLAPPING_NOTE: &str = "(state changed multiple times; showing the latest)";
 
 impl Event {
-    pub fn channel_id(&self) -> &ChannelId {
-        &self.channel_id
+    pub fn destination(&self) -> &Destination {
+        &self.destination
     }
 
     #[must_use]
@@ -19,15 +24,61 @@ impl Event {
 
     #[must_use]
     pub fn can_merged(&self, other: &Event) -> bool {
-        self.channel_id == other.channel_id && self.kind == other.kind
+        self.destination == other.destination && self.kind == other.kind
     }
 
     pub fn merge(&mut self, other: Event) -> Option<Event> {
+        if self.can_merged(&other) && self.collapse && other.collapse {
+            self.body.0 = format!("{}\n{FLAPPING_NOTE}\n", other.body.0.trim_end());
+            self.deliveries.extend(other.deliveries);
+            return None;
+        }
         if self.can_merged(&other) {
             self.body.0 += "\n";
             self.body.0 += other.body.as_ref();
+            self.deliveries.extend(other.deliveries);
             return None;
         }
         Some(other)
     }
 }
+
+impl fmt::Display for Destination {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Channel(channel_id) => write!(f, "channel:{channel_id}"),
+            Self::DirectMessage(user_id) => write!(f, "dm:{user_id}"),
+            Self::Sink(sink_id) => write!(f, "sink:{sink_id}"),
+        }
+    }
+}
+
+impl SinkKind {
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Slack => "slack",
+            Self::Discord => "discord",
+            Self::Http => "http",
+        }
+    }
+}
+
+impl FromStr for SinkKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "slack" => Ok(Self::Slack),
+            "discord" => Ok(Self::Discord),
+            "http" => Ok(Self::Http),
+            _ => Err(format!("unexpected sink kind: {s}")),
+        }
+    }
+}
+
+impl fmt::Display for SinkKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
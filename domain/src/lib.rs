@@ -1,3 +1,4 @@
+mod delivery;
 pub mod error;
 mod event;
 mod group;
@@ -7,20 +8,73 @@ pub mod owner;
 mod user;
 mod webhook;
 
+use std::time::SystemTime;
+
 use serde::{Deserialize, Serialize};
 
 pub use error::Failure;
 // id
-pub use newtypes::{ChannelId, GroupId, MessageId, OwnerId, StampId, UserId, WebhookId};
+pub use newtypes::{
+    ChannelId, DeliveryId, GroupId, MessageId, OwnerId, SinkId, StampId, UserId, WebhookId,
+};
 // string
-pub use newtypes::{EventBody, EventKind, GroupName, UserName};
+pub use newtypes::{
+    EventBody, EventFilter, EventKind, GroupName, RulePattern, SinkUrl, UserName, WebhookSecret,
+    WebhookTemplate,
+};
 
 #[must_use]
 #[derive(Clone, Debug)]
 pub struct Event {
-    pub channel_id: ChannelId,
+    pub destination: Destination,
     pub kind: EventKind,
     pub body: EventBody,
+    /// 同じ`kind`のイベントを連結せず、最新のもので置き換えるか
+    ///
+    /// 死活監視のように状態だけが意味を持つ通知で、短時間の状態の揺れ(flapping)をまとめるために使う
+    pub collapse: bool,
+    /// このイベントの元になった配信の記録。traQへの投稿に失敗したときに更新する
+    pub deliveries: Vec<DeliveryId>,
+}
+
+/// イベントの投稿先
+#[must_use]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(tag = "type", content = "id", rename_all = "snake_case")]
+pub enum Destination {
+    Channel(ChannelId),
+    /// ユーザーへのDM
+    DirectMessage(UserId),
+    /// traQ以外への転送。URLを含めないよう、送信時に`Repository::find_sink`で引く
+    Sink(SinkId),
+}
+
+/// traQ以外の転送先
+#[must_use]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct Sink {
+    pub id: SinkId,
+    pub kind: SinkKind,
+    /// Incoming WebhookのURLなど。認証情報を含むことがあるのでログに出さない
+    pub url: SinkUrl,
+}
+
+#[must_use]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SinkKind {
+    /// Slack Incoming Webhook
+    Slack,
+    /// Discord Webhook
+    Discord,
+    /// 任意のHTTPエンドポイントへのJSON POST
+    Http,
+}
+
+/// traQ以外へのイベントの転送
+#[must_use]
+pub trait EventSink: Send + Sync + 'static {
+    fn send(&self, sink: &Sink, event: &Event) -> impl Future<Output = Result<(), Failure>> + Send;
 }
 
 #[must_use]
@@ -64,6 +118,95 @@ pub struct Webhook {
     pub id: WebhookId,
     pub channel_id: ChannelId,
     pub owner: Owner,
+    /// `channel_id`の代わりに所有者へのDMに投稿するか。所有者が1人のときのみ有効
+    #[serde(default)]
+    pub dm: bool,
+    /// 署名検証などに使う。外部に漏れないようにシリアライズしない
+    #[serde(default, skip_serializing)]
+    pub secret: Option<WebhookSecret>,
+    /// generic webhookのメッセージテンプレート
+    #[serde(default)]
+    pub template: Option<WebhookTemplate>,
+    /// `channel_id`以外の投稿先
+    #[serde(default)]
+    pub channels: Vec<WebhookChannel>,
+    /// 先頭から順に評価し、最初に一致したルールの投稿先を`channel_id`の代わりに使う
+    #[serde(default)]
+    pub rules: Vec<WebhookRule>,
+    /// 全てのイベントを転送する。URLが外部に漏れないようにシリアライズしない
+    #[serde(default, skip_serializing)]
+    pub sinks: Vec<Sink>,
+}
+
+/// Webhookの追加の投稿先
+#[must_use]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WebhookChannel {
+    pub channel_id: ChannelId,
+    /// 投稿するイベントの種類。`None`なら全てのイベントを投稿する
+    #[serde(default)]
+    pub filter: Option<EventFilter>,
+}
+
+/// ペイロードの内容による投稿先の振り分けルール
+#[must_use]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WebhookRule {
+    pub field: RuleField,
+    /// `*`は任意の文字列にマッチする
+    pub pattern: RulePattern,
+    pub channel_id: ChannelId,
+}
+
+/// ルールで比較するペイロードの値
+#[must_use]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleField {
+    /// `owner/name`形式のリポジトリ名
+    Repo,
+    /// Issue・Pull Requestなどのラベル
+    Label,
+    /// GitHub Actionsのワークフローなどの結果 (`success`, `failure`, ...)
+    Conclusion,
+    /// イベントの種類。追加の投稿先のフィルタと同じもの
+    Event,
+}
+
+/// 受け取ったWebhookの記録
+#[must_use]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Delivery {
+    /// UUIDv7。受け取った時刻を含む
+    pub id: DeliveryId,
+    pub webhook_id: WebhookId,
+    /// `github`, `sentry`などの受け口の種類
+    pub provider: String,
+    /// `X-GitHub-Event`などのイベントの種類を表すヘッダーの値
+    pub event: Option<String>,
+    /// `X-GitHub-Delivery`などの送信元が付けたID
+    pub remote_id: Option<String>,
+    pub status: DeliveryStatus,
+    /// `status`が`Rejected`・`Error`のときの理由
+    pub error: Option<String>,
+    pub payload: String,
+}
+
+/// 受け取ったWebhookの処理結果
+#[must_use]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    /// イベントを投稿待ちに追加した
+    Accepted,
+    /// 投稿するイベントがなかった
+    Filtered,
+    /// 署名の不一致やペイロードの不正
+    Rejected,
+    /// 内部エラー
+    Error,
+    /// 投稿待ちに追加したが、traQへの投稿に失敗した
+    Failed,
 }
 
 #[must_use]
@@ -71,9 +214,12 @@ pub trait Repository: Send + Sync + 'static {
     fn add_webhook(&self, webhook: &Webhook) -> impl Future<Output = Result<(), Failure>> + Send;
     fn remove_webhook(&self, webhook: &Webhook)
     -> impl Future<Output = Result<(), Failure>> + Send;
+    fn update_webhook(&self, webhook: &Webhook)
+    -> impl Future<Output = Result<(), Failure>> + Send;
     fn list_webhooks(&self) -> impl Future<Output = Result<Vec<Webhook>, Failure>> + Send;
     fn find_webhook(&self, id: &WebhookId)
     -> impl Future<Output = Result<Webhook, Failure>> + Send;
+    fn find_sink(&self, id: &SinkId) -> impl Future<Output = Result<Sink, Failure>> + Send;
     fn filter_webhook_by_owner(
         &self,
         owner: &Owner,
@@ -86,6 +232,25 @@ pub trait Repository: Send + Sync + 'static {
         &self,
         user: &User,
     ) -> impl Future<Output = Result<Vec<Webhook>, Failure>> + Send;
+    fn add_delivery(&self, delivery: &Delivery)
+    -> impl Future<Output = Result<(), Failure>> + Send;
+    fn update_delivery_status(
+        &self,
+        id: &DeliveryId,
+        status: DeliveryStatus,
+        error: Option<&str>,
+    ) -> impl Future<Output = Result<(), Failure>> + Send;
+    /// 新しい順に最大`limit`件
+    fn filter_deliveries_by_webhook(
+        &self,
+        webhook_id: &WebhookId,
+        limit: usize,
+    ) -> impl Future<Output = Result<Vec<Delivery>, Failure>> + Send;
+    /// `before`より前に受け取った記録を削除する
+    fn remove_deliveries_before(
+        &self,
+        before: SystemTime,
+    ) -> impl Future<Output = Result<(), Failure>> + Send;
 }
 
 #[must_use]
@@ -162,8 +327,10 @@ pub trait Infra: Send + Sync + 'static {
     type Repo: Repository;
     type TClient: TraqClient;
     type ESub: EventSubscriber;
+    type ESink: EventSink;
 
     fn repo(&self) -> &Self::Repo;
     fn traq_client(&self) -> &Self::TClient;
     fn event_subscriber(&self) -> &Self::ESub;
+    fn event_sink(&self) -> &Self::ESink;
 }
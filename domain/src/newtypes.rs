@@ -1,7 +1,9 @@
 crate::macros::newtype! {id Channel}
+crate::macros::newtype! {id Delivery}
 crate::macros::newtype! {id Group}
 crate::macros::newtype! {id Message}
 crate::macros::newtype! {id Owner}
+crate::macros::newtype! {id Sink}
 crate::macros::newtype! {id Stamp}
 crate::macros::newtype! {id User}
 crate::macros::newtype! {id Webhook}
@@ -10,3 +12,8 @@ crate::macros::newtype! {string EventKind}
 crate::macros::newtype! {string EventBody}
 crate::macros::newtype! {string UserName}
 crate::macros::newtype! {string GroupName}
+crate::macros::newtype! {string WebhookSecret}
+crate::macros::newtype! {string WebhookTemplate}
+crate::macros::newtype! {string EventFilter}
+crate::macros::newtype! {string RulePattern}
+crate::macros::newtype! {string SinkUrl}
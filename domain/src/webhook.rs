@@ -1,5 +1,10 @@
-use crate::newtypes::{ChannelId, WebhookId};
-use crate::{Owner, Webhook};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::newtypes::{
+    ChannelId, EventFilter, RulePattern, WebhookId, WebhookSecret, WebhookTemplate,
+};
+use crate::{Destination, Owner, RuleField, Sink, Webhook, WebhookChannel, WebhookRule};
 
 impl Webhook {
     pub fn new(id: WebhookId, channel_id: ChannelId, owner: Owner) -> Self {
@@ -7,6 +12,331 @@ impl Webhook {
             id,
             channel_id,
             owner,
+            dm: false,
+            secret: None,
+            template: None,
+            channels: vec![],
+            rules: vec![],
+            sinks: vec![],
+        }
+    }
+
+    pub fn with_dm(self, dm: bool) -> Self {
+        Self { dm, ..self }
+    }
+
+    /// ルールに一致しなかったときの投稿先。DMは所有者が1人のときのみ
+    pub fn destination(&self) -> Destination {
+        match &self.owner {
+            Owner::SingleUser(user) if self.dm => Destination::DirectMessage(user.id),
+            _ => Destination::Channel(self.channel_id),
+        }
+    }
+
+    pub fn with_secret(self, secret: Option<WebhookSecret>) -> Self {
+        Self { secret, ..self }
+    }
+
+    #[must_use]
+    pub fn secret(&self) -> Option<&str> {
+        self.secret.as_ref().map(AsRef::as_ref)
+    }
+
+    pub fn with_template(self, template: Option<WebhookTemplate>) -> Self {
+        Self { template, ..self }
+    }
+
+    #[must_use]
+    pub fn template(&self) -> Option<&str> {
+        self.template.as_ref().map(AsRef::as_ref)
+    }
+
+    /// 同じチャンネルが既にあれば置き換える
+    pub fn with_channel(mut self, channel: WebhookChannel) -> Self {
+        self.channels.retain(|c| c.channel_id != channel.channel_id);
+        self.channels.push(channel);
+        self
+    }
+
+    pub fn without_channel(mut self, channel_id: &ChannelId) -> Self {
+        self.channels.retain(|c| &c.channel_id != channel_id);
+        self
+    }
+
+    pub fn with_rule(mut self, rule: WebhookRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// `index`番目のルールを削除する。範囲外なら何もしない
+    pub fn without_rule(mut self, index: usize) -> Self {
+        if index < self.rules.len() {
+            let _ = self.rules.remove(index);
+        }
+        self
+    }
+
+    pub fn with_sink(mut self, sink: Sink) -> Self {
+        self.sinks.push(sink);
+        self
+    }
+
+    /// `index`番目の転送先を削除する。範囲外なら何もしない
+    pub fn without_sink(mut self, index: usize) -> Self {
+        if index < self.sinks.len() {
+            let _ = self.sinks.remove(index);
+        }
+        self
+    }
+
+    /// ルールで振り分けた投稿先。どのルールにも一致しなければ`destination()`
+    ///
+    /// `values`はルールで比較するペイロードの値を返す
+    pub fn route<F>(&self, values: F) -> Destination
+    where
+        F: Fn(RuleField) -> Vec<String>,
+    {
+        self.rules
+            .iter()
+            .find(|r| values(r.field).iter().any(|v| r.matches(v)))
+            .map_or_else(
+                || self.destination(),
+                |r| Destination::Channel(r.channel_id),
+            )
+    }
+
+    /// `event_type`のイベントの投稿先。`primary`と転送先には常に投稿する
+    pub fn destinations<'a>(
+        &'a self,
+        primary: Destination,
+        event_type: &'a str,
+    ) -> impl Iterator<Item = Destination> + 'a {
+        let excluded = primary.clone();
+        let channels = self
+            .channels
+            .iter()
+            .filter(move |c| c.accepts(event_type))
+            .map(|c| Destination::Channel(c.channel_id))
+            .filter(move |d| d != &excluded);
+        let sinks = self.sinks.iter().map(|s| Destination::Sink(s.id));
+        std::iter::once(primary).chain(channels).chain(sinks)
+    }
+}
+
+impl WebhookChannel {
+    pub fn new(channel_id: ChannelId, filter: Option<EventFilter>) -> Self {
+        Self { channel_id, filter }
+    }
+
+    #[must_use]
+    pub fn filter(&self) -> Option<&str> {
+        self.filter.as_ref().map(AsRef::as_ref)
+    }
+
+    /// `filter`はカンマ区切りのパターンで、`*`は任意の文字列にマッチする
+    #[must_use]
+    pub fn accepts(&self, event_type: &str) -> bool {
+        let Some(filter) = self.filter() else {
+            return true;
+        };
+        filter
+            .split(',')
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .any(|p| glob_match(p, event_type))
+    }
+}
+
+impl WebhookRule {
+    pub fn new(field: RuleField, pattern: RulePattern, channel_id: ChannelId) -> Self {
+        Self {
+            field,
+            pattern,
+            channel_id,
+        }
+    }
+
+    #[must_use]
+    pub fn matches(&self, value: &str) -> bool {
+        glob_match(self.pattern.as_ref(), value)
+    }
+}
+
+impl RuleField {
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Repo => "repo",
+            Self::Label => "label",
+            Self::Conclusion => "conclusion",
+            Self::Event => "event",
+        }
+    }
+}
+
+impl FromStr for RuleField {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "repo" => Ok(Self::Repo),
+            "label" => Ok(Self::Label),
+            "conclusion" => Ok(Self::Conclusion),
+            "event" => Ok(Self::Event),
+            _ => Err(format!("unexpected rule field: {s}")),
         }
     }
 }
+
+impl fmt::Display for RuleField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+fn glob_match(pattern: &str, s: &str) -> bool {
+    let Some((head, rest)) = pattern.split_once('*') else {
+        return pattern == s;
+    };
+    let Some(mut s) = s.strip_prefix(head) else {
+        return false;
+    };
+    let mut parts: Vec<&str> = rest.split('*').collect();
+    let tail = parts.pop().unwrap_or_default();
+    for part in parts {
+        let Some(i) = s.find(part) else {
+            return false;
+        };
+        s = &s[i + part.len()..];
+    }
+    s.len() >= tail.len() && s.ends_with(tail)
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use crate::{User, UserId};
+
+    use super::*;
+
+    fn channel(n: u128) -> ChannelId {
+        ChannelId(Uuid::from_u128(n))
+    }
+
+    fn webhook() -> Webhook {
+        let owner = Owner::SingleUser(User {
+            id: UserId(Uuid::from_u128(1)),
+            name: "user".to_string().into(),
+        });
+        Webhook::new(WebhookId(Uuid::from_u128(1)), channel(1), owner)
+    }
+
+    fn filtered(n: u128, filter: &str) -> WebhookChannel {
+        WebhookChannel::new(channel(n), Some(filter.to_string().into()))
+    }
+
+    #[test]
+    fn glob_match_without_wildcard_is_exact() {
+        assert!(glob_match("push", "push"));
+        assert!(!glob_match("push", "pushed"));
+        assert!(!glob_match("push", "pus"));
+    }
+
+    #[test]
+    fn glob_match_wildcards() {
+        assert!(glob_match("*", ""));
+        assert!(glob_match("pull_request*", "pull_request_review"));
+        assert!(glob_match("*.created", "issue.created"));
+        assert!(glob_match("a*b*c", "aXbYc"));
+        assert!(glob_match("a*b*c", "abc"));
+        assert!(!glob_match("a*b*c", "aXc"));
+        // 先頭と末尾が重なる場合
+        assert!(!glob_match("a*a", "a"));
+        assert!(glob_match("a*a", "aa"));
+    }
+
+    #[test]
+    fn channel_without_filter_accepts_all() {
+        let c = WebhookChannel::new(channel(2), None);
+        assert!(c.accepts("push"));
+        assert!(c.accepts(""));
+    }
+
+    #[test]
+    fn channel_filter_is_comma_separated() {
+        let c = filtered(2, "push, issues ,pull_request*");
+        assert!(c.accepts("push"));
+        assert!(c.accepts("issues"));
+        assert!(c.accepts("pull_request_review"));
+        assert!(!c.accepts("issue_comment"));
+    }
+
+    #[test]
+    fn channel_filter_ignores_empty_patterns() {
+        let c = filtered(2, ",, ");
+        assert!(!c.accepts(""));
+        assert!(!c.accepts("push"));
+    }
+
+    #[test]
+    fn destinations_filter_channels_and_skip_primary() {
+        let webhook = webhook()
+            .with_channel(filtered(2, "push"))
+            .with_channel(filtered(3, "issues"))
+            .with_channel(WebhookChannel::new(channel(4), None));
+        let primary = Destination::Channel(channel(4));
+        let destinations: Vec<_> = webhook.destinations(primary, "push").collect();
+        assert_eq!(
+            destinations,
+            [
+                Destination::Channel(channel(4)),
+                Destination::Channel(channel(2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn route_uses_first_matching_rule() {
+        let webhook = webhook()
+            .with_rule(WebhookRule::new(
+                RuleField::Label,
+                "bug".to_string().into(),
+                channel(2),
+            ))
+            .with_rule(WebhookRule::new(
+                RuleField::Repo,
+                "org/*".to_string().into(),
+                channel(3),
+            ));
+        let labeled = |labels: &'static [&'static str]| {
+            move |field| match field {
+                RuleField::Repo => vec!["org/app".to_string()],
+                RuleField::Label => labels.iter().map(ToString::to_string).collect(),
+                _ => vec![],
+            }
+        };
+        assert_eq!(
+            webhook.route(labeled(&["docs", "bug"])),
+            Destination::Channel(channel(2))
+        );
+        assert_eq!(
+            webhook.route(labeled(&[])),
+            Destination::Channel(channel(3))
+        );
+    }
+
+    #[test]
+    fn route_falls_back_to_destination() {
+        let webhook = webhook().with_dm(true).with_rule(WebhookRule::new(
+            RuleField::Event,
+            "push".to_string().into(),
+            channel(2),
+        ));
+        let destination = webhook.route(|_| vec!["issues".to_string()]);
+        assert_eq!(
+            destination,
+            Destination::DirectMessage(UserId(Uuid::from_u128(1)))
+        );
+    }
+}
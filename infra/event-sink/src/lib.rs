@@ -0,0 +1,195 @@
+//! traQ以外の転送先 (Slack, Discord, 任意のHTTPエンドポイント) へのイベントの転送
+
+use std::time::Duration;
+
+use anyhow::Context;
+use reqwest::StatusCode;
+use serde_json::{Value, json};
+
+use domain::{Event, EventSink, Failure, Sink, SinkKind};
+
+/// 1回の送信を待つ時間
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Discordのメッセージの最大文字数
+const DISCORD_MAX_LENGTH: usize = 2000;
+
+#[must_use]
+#[derive(Debug, Clone, Default)]
+pub struct EventSinkImpl {
+    client: reqwest::Client,
+}
+
+impl EventSinkImpl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// プロキシなどを設定した`reqwest::Client`を使う
+    pub fn with_client(client: reqwest::Client) -> Self {
+        Self { client }
+    }
+}
+
+impl EventSink for EventSinkImpl {
+    #[tracing::instrument(skip_all, fields(kind = %sink.kind))]
+    async fn send(&self, sink: &Sink, event: &Event) -> Result<(), Failure> {
+        let body = payload(sink.kind, event);
+        let res = self
+            .client
+            .post(sink.url.as_ref() as &str)
+            .timeout(TIMEOUT)
+            .json(&body)
+            .send()
+            .await
+            .context("Failed to send event to sink")?;
+        let status = res.status();
+        tracing::debug!(%status, "sent event to sink");
+        if status.is_success() {
+            return Ok(());
+        }
+        // 429と5xxは一時的なものとして再送させる
+        if status.is_client_error() && status != StatusCode::TOO_MANY_REQUESTS {
+            let message = format!("Sink responded with {status}");
+            return Err(Failure::reject_bad_request(message));
+        }
+        Err(anyhow::anyhow!("Sink responded with {status}").into())
+    }
+}
+
+fn payload(kind: SinkKind, event: &Event) -> Value {
+    let body = event.body();
+    match kind {
+        // https://api.slack.com/messaging/webhooks
+        SinkKind::Slack => json!({ "text": body }),
+        // https://discord.com/developers/docs/resources/webhook#execute-webhook
+        SinkKind::Discord => json!({ "content": truncate(&body, DISCORD_MAX_LENGTH) }),
+        SinkKind::Http => json!({ "kind": event.kind(), "body": body }),
+    }
+}
+
+fn truncate(s: &str, max_chars: usize) -> String {
+    const SUFFIX: &str = "…";
+    if s.chars().count() <= max_chars {
+        return s.to_string();
+    }
+    let mut truncated: String = s.chars().take(max_chars - SUFFIX.chars().count()).collect();
+    truncated += SUFFIX;
+    truncated
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+    use wiremock::matchers::{body_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use domain::{ChannelId, Destination, SinkId};
+
+    use super::*;
+
+    fn sink(server: &MockServer, kind: SinkKind) -> Sink {
+        Sink {
+            id: SinkId(Uuid::nil()),
+            kind,
+            url: format!("{}/hook", server.uri()).into(),
+        }
+    }
+
+    fn event(body: &str) -> Event {
+        Event {
+            destination: Destination::Channel(ChannelId(Uuid::nil())),
+            kind: "github".to_string().into(),
+            body: body.to_string().into(),
+            collapse: false,
+            deliveries: vec![],
+        }
+    }
+
+    async fn respond_with(status: u16) -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/hook"))
+            .respond_with(ResponseTemplate::new(status))
+            .mount(&server)
+            .await;
+        server
+    }
+
+    #[tokio::test]
+    async fn sends_slack_text() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/hook"))
+            .and(body_json(json!({ "text": "hello" })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let sink = sink(&server, SinkKind::Slack);
+        EventSinkImpl::new()
+            .send(&sink, &event("hello"))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn sends_truncated_discord_content() {
+        let body = "a".repeat(DISCORD_MAX_LENGTH + 1);
+        let content = format!("{}…", "a".repeat(DISCORD_MAX_LENGTH - 1));
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/hook"))
+            .and(body_json(json!({ "content": content })))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let sink = sink(&server, SinkKind::Discord);
+        EventSinkImpl::new()
+            .send(&sink, &event(&body))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn sends_http_kind_and_body() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/hook"))
+            .and(body_json(json!({ "kind": "github", "body": "hello" })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let sink = sink(&server, SinkKind::Http);
+        EventSinkImpl::new()
+            .send(&sink, &event("hello"))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn rejects_on_client_error() {
+        let server = respond_with(404).await;
+        let sink = sink(&server, SinkKind::Http);
+        let res = EventSinkImpl::new().send(&sink, &event("hello")).await;
+        assert!(matches!(res, Err(Failure::Reject(_))));
+    }
+
+    #[tokio::test]
+    async fn errors_on_too_many_requests() {
+        let server = respond_with(429).await;
+        let sink = sink(&server, SinkKind::Http);
+        let res = EventSinkImpl::new().send(&sink, &event("hello")).await;
+        assert!(matches!(res, Err(Failure::Error(_))));
+    }
+
+    #[tokio::test]
+    async fn errors_on_server_error() {
+        let server = respond_with(503).await;
+        let sink = sink(&server, SinkKind::Http);
+        let res = EventSinkImpl::new().send(&sink, &event("hello")).await;
+        assert!(matches!(res, Err(Failure::Error(_))));
+    }
+}
@@ -0,0 +1,2 @@
+ALTER TABLE `webhooks_v2`
+ADD COLUMN `secret` VARCHAR(255) NULL DEFAULT NULL;
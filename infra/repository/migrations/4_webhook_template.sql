@@ -0,0 +1,2 @@
+ALTER TABLE `webhooks_v2`
+ADD COLUMN `template` TEXT NULL DEFAULT NULL;
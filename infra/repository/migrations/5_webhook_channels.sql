@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS `webhook_channels_v2` (
+    `webhook_id` BINARY(16) NOT NULL,
+    `channel_id` BINARY(16) NOT NULL,
+    `filter` VARCHAR(255) NULL DEFAULT NULL,
+    PRIMARY KEY (`webhook_id`, `channel_id`),
+    FOREIGN KEY (`webhook_id`) REFERENCES `webhooks_v2` (`id`) ON DELETE CASCADE
+);
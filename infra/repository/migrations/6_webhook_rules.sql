@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS `webhook_rules_v2` (
+    `webhook_id` BINARY(16) NOT NULL,
+    `position` INT UNSIGNED NOT NULL,
+    `field` ENUM('repo', 'label', 'conclusion', 'event') NOT NULL,
+    `pattern` VARCHAR(255) NOT NULL,
+    `channel_id` BINARY(16) NOT NULL,
+    PRIMARY KEY (`webhook_id`, `position`),
+    FOREIGN KEY (`webhook_id`) REFERENCES `webhooks_v2` (`id`) ON DELETE CASCADE
+);
@@ -0,0 +1,2 @@
+ALTER TABLE `webhooks_v2`
+ADD COLUMN `dm` BOOLEAN NOT NULL DEFAULT FALSE;
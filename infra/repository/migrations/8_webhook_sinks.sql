@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS `webhook_sinks_v2` (
+    `id` BINARY(16) NOT NULL UNIQUE,
+    `webhook_id` BINARY(16) NOT NULL,
+    `position` INT UNSIGNED NOT NULL,
+    `kind` ENUM('slack', 'discord', 'http') NOT NULL,
+    `url` TEXT NOT NULL,
+    PRIMARY KEY (`webhook_id`, `position`),
+    FOREIGN KEY (`webhook_id`) REFERENCES `webhooks_v2` (`id`) ON DELETE CASCADE
+);
@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS `webhook_deliveries_v2` (
+    `id` BINARY(16) NOT NULL,
+    `webhook_id` BINARY(16) NOT NULL,
+    `provider` VARCHAR(32) NOT NULL,
+    `event` VARCHAR(255) NULL,
+    `remote_id` VARCHAR(255) NULL,
+    `status` ENUM('accepted', 'filtered', 'rejected', 'error', 'failed') NOT NULL,
+    `error` TEXT NULL,
+    `payload` MEDIUMBLOB NOT NULL,
+    PRIMARY KEY (`id`),
+    INDEX (`webhook_id`, `id`),
+    FOREIGN KEY (`webhook_id`) REFERENCES `webhooks_v2` (`id`) ON DELETE CASCADE
+);
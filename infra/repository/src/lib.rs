@@ -1,9 +1,13 @@
-use sqlx::MySqlPool;
+use std::time::SystemTime;
+
+use anyhow::Context;
 use sqlx::migrate::Migrator;
+use sqlx::{MySqlConnection, MySqlPool};
 
 use domain::{
-    ChannelId, Failure, Group, GroupId, Owner, OwnerId, OwnerKind, Repository, User, Webhook,
-    WebhookId,
+    ChannelId, Delivery, DeliveryId, DeliveryStatus, EventFilter, Failure, Group, GroupId, Owner,
+    OwnerId, OwnerKind, Repository, RulePattern, Sink, SinkId, SinkUrl, User, Webhook,
+    WebhookChannel, WebhookId, WebhookRule, WebhookSecret, WebhookTemplate,
 };
 
 pub(crate) mod model;
@@ -67,10 +71,45 @@ impl RepositoryImpl {
             };
             Owner::SingleUser(user)
         };
+        let channels = self
+            .filter_webhook_channels_by_wid(&w.id)
+            .await?
+            .into_iter()
+            .map(|wc| WebhookChannel {
+                channel_id: wc.channel_id,
+                filter: wc.filter.map(EventFilter::from),
+            })
+            .collect();
+        let rules = self
+            .filter_webhook_rules_by_wid(&w.id)
+            .await?
+            .into_iter()
+            .map(|wr| WebhookRule {
+                field: wr.field,
+                pattern: RulePattern::from(wr.pattern),
+                channel_id: wr.channel_id,
+            })
+            .collect();
+        let sinks = self
+            .filter_webhook_sinks_by_wid(&w.id)
+            .await?
+            .into_iter()
+            .map(|ws| Sink {
+                id: ws.id,
+                kind: ws.kind,
+                url: SinkUrl::from(ws.url),
+            })
+            .collect();
         Ok(Webhook {
             id: w.id,
             channel_id: w.channel_id,
             owner,
+            dm: w.dm,
+            secret: w.secret.clone().map(WebhookSecret::from),
+            template: w.template.clone().map(WebhookTemplate::from),
+            channels,
+            rules,
+            sinks,
         })
     }
 
@@ -82,6 +121,63 @@ impl RepositoryImpl {
         let webhooks = futures::future::try_join_all(it).await?;
         Ok(webhooks)
     }
+
+    async fn replace_webhook_channels(
+        conn: &mut MySqlConnection,
+        webhook: &Webhook,
+    ) -> Result<(), Failure> {
+        let wcs = webhook
+            .channels
+            .iter()
+            .map(|c| crate::model::WebhookChannel {
+                webhook_id: webhook.id,
+                channel_id: c.channel_id,
+                filter: c.filter.clone().map(String::from),
+            })
+            .collect::<Vec<_>>();
+        Self::delete_webhook_channels_by_wid(&mut *conn, &webhook.id).await?;
+        Self::create_webhook_channels(conn, &wcs).await
+    }
+
+    async fn replace_webhook_rules(
+        conn: &mut MySqlConnection,
+        webhook: &Webhook,
+    ) -> Result<(), Failure> {
+        let wrs = webhook
+            .rules
+            .iter()
+            .zip(0..)
+            .map(|(r, position)| crate::model::WebhookRule {
+                webhook_id: webhook.id,
+                position,
+                field: r.field,
+                pattern: r.pattern.clone().into(),
+                channel_id: r.channel_id,
+            })
+            .collect::<Vec<_>>();
+        Self::delete_webhook_rules_by_wid(&mut *conn, &webhook.id).await?;
+        Self::create_webhook_rules(conn, &wrs).await
+    }
+
+    async fn replace_webhook_sinks(
+        conn: &mut MySqlConnection,
+        webhook: &Webhook,
+    ) -> Result<(), Failure> {
+        let wss = webhook
+            .sinks
+            .iter()
+            .zip(0..)
+            .map(|(s, position)| crate::model::WebhookSink {
+                id: s.id,
+                webhook_id: webhook.id,
+                position,
+                kind: s.kind,
+                url: s.url.clone().into(),
+            })
+            .collect::<Vec<_>>();
+        Self::delete_webhook_sinks_by_wid(&mut *conn, &webhook.id).await?;
+        Self::create_webhook_sinks(conn, &wss).await
+    }
 }
 
 impl Repository for RepositoryImpl {
@@ -90,15 +186,26 @@ impl Repository for RepositoryImpl {
             id: webhook.id,
             channel_id: webhook.channel_id,
             owner_id: webhook.owner.id(),
+            secret: webhook.secret.clone().map(String::from),
+            template: webhook.template.clone().map(String::from),
+            dm: webhook.dm,
         };
-        self.create_webhook(w).await?;
+        let mut tx = self
+            .0
+            .begin()
+            .await
+            .context("Failed to begin transaction")?;
+        Self::create_webhook(&mut tx, w).await?;
+        Self::replace_webhook_channels(&mut tx, webhook).await?;
+        Self::replace_webhook_rules(&mut tx, webhook).await?;
+        Self::replace_webhook_sinks(&mut tx, webhook).await?;
         let o = crate::model::Owner {
             id: webhook.owner.id(),
             name: webhook.owner.name().to_string(),
             kind: webhook.owner.kind(),
         };
         // 既に存在するかもしれないのでcreate_ignoreで
-        self.create_ignore_owners(&[o]).await?;
+        Self::create_ignore_owners(&mut tx, &[o]).await?;
         match &webhook.owner {
             Owner::Group(group) => {
                 use crate::model::Group;
@@ -108,7 +215,7 @@ impl Repository for RepositoryImpl {
                     id: group.id,
                     name: group.name.clone().into(),
                 };
-                self.create_ignore_groups(&[g]).await?;
+                Self::create_ignore_groups(&mut tx, &[g]).await?;
                 let gms = group
                     .members
                     .iter()
@@ -117,7 +224,7 @@ impl Repository for RepositoryImpl {
                         group_id: group.id,
                     })
                     .collect::<Vec<_>>();
-                self.create_ignore_group_members(&gms).await?;
+                Self::create_ignore_group_members(&mut tx, &gms).await?;
                 let us = group
                     .members
                     .iter()
@@ -126,16 +233,17 @@ impl Repository for RepositoryImpl {
                         name: u.name.clone().into(),
                     })
                     .collect::<Vec<_>>();
-                self.create_ignore_users(&us).await?;
+                Self::create_ignore_users(&mut tx, &us).await?;
             }
             Owner::SingleUser(user) => {
                 let u = crate::model::User {
                     id: user.id,
                     name: user.name.clone().into(),
                 };
-                self.create_ignore_users(&[u]).await?;
+                Self::create_ignore_users(&mut tx, &[u]).await?;
             }
         }
+        tx.commit().await.context("Failed to commit transaction")?;
         Ok(())
     }
 
@@ -143,6 +251,28 @@ impl Repository for RepositoryImpl {
         self.delete_webhook(&webhook.id).await
     }
 
+    async fn update_webhook(&self, webhook: &Webhook) -> Result<(), Failure> {
+        let w = crate::model::Webhook {
+            id: webhook.id,
+            channel_id: webhook.channel_id,
+            owner_id: webhook.owner.id(),
+            secret: webhook.secret.clone().map(String::from),
+            template: webhook.template.clone().map(String::from),
+            dm: webhook.dm,
+        };
+        let mut tx = self
+            .0
+            .begin()
+            .await
+            .context("Failed to begin transaction")?;
+        Self::update_webhook(&mut tx, &webhook.id, w).await?;
+        Self::replace_webhook_channels(&mut tx, webhook).await?;
+        Self::replace_webhook_rules(&mut tx, webhook).await?;
+        Self::replace_webhook_sinks(&mut tx, webhook).await?;
+        tx.commit().await.context("Failed to commit transaction")?;
+        Ok(())
+    }
+
     async fn list_webhooks(&self) -> Result<Vec<Webhook>, Failure> {
         let ws = self.read_webhooks().await?;
         let webhooks = self.complete_webhooks(&ws).await?;
@@ -155,6 +285,15 @@ impl Repository for RepositoryImpl {
         Ok(webhook)
     }
 
+    async fn find_sink(&self, id: &SinkId) -> Result<Sink, Failure> {
+        let ws = self.find_webhook_sink(id).await?;
+        Ok(Sink {
+            id: ws.id,
+            kind: ws.kind,
+            url: SinkUrl::from(ws.url),
+        })
+    }
+
     async fn filter_webhook_by_owner(&self, owner: &Owner) -> Result<Vec<Webhook>, Failure> {
         let ws = self.filter_webhooks_by_oid(owner.id()).await?;
         self.complete_webhooks(&ws).await
@@ -178,4 +317,62 @@ impl Repository for RepositoryImpl {
         let ws = self.filter_webhooks_by_oids(&oids).await?;
         self.complete_webhooks(&ws).await
     }
+
+    async fn add_delivery(&self, delivery: &Delivery) -> Result<(), Failure> {
+        let d = crate::model::Delivery {
+            id: delivery.id,
+            webhook_id: delivery.webhook_id,
+            provider: delivery.provider.clone(),
+            event: delivery.event.clone(),
+            remote_id: delivery.remote_id.clone(),
+            status: delivery.status,
+            error: delivery.error.clone(),
+            payload: crate::model::compress_payload(&delivery.payload)?,
+        };
+        self.create_delivery(&d).await
+    }
+
+    async fn update_delivery_status(
+        &self,
+        id: &DeliveryId,
+        status: DeliveryStatus,
+        error: Option<&str>,
+    ) -> Result<(), Failure> {
+        self.update_delivery(id, status, error).await
+    }
+
+    async fn filter_deliveries_by_webhook(
+        &self,
+        webhook_id: &WebhookId,
+        limit: usize,
+    ) -> Result<Vec<Delivery>, Failure> {
+        let limit = u64::try_from(limit).unwrap_or(u64::MAX);
+        let ds = self.filter_deliveries_by_wid(webhook_id, limit).await?;
+        ds.into_iter()
+            .map(|d| {
+                let payload = crate::model::decompress_payload(&d.payload)?;
+                Ok(Delivery {
+                    id: d.id,
+                    webhook_id: d.webhook_id,
+                    provider: d.provider,
+                    event: d.event,
+                    remote_id: d.remote_id,
+                    status: d.status,
+                    error: d.error,
+                    payload,
+                })
+            })
+            .collect()
+    }
+
+    async fn remove_deliveries_before(&self, before: SystemTime) -> Result<(), Failure> {
+        // UUIDv7は先頭48bitがミリ秒単位の時刻なので、残りを0にしたIDと比較すればよい
+        let millis = before
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        let millis = u64::try_from(millis).unwrap_or(u64::MAX);
+        let before = uuid::Builder::from_unix_timestamp_millis(millis, &[0; 10]).into_uuid();
+        self.delete_deliveries_before(&DeliveryId(before)).await
+    }
 }
@@ -1,11 +1,19 @@
+mod delivery;
 mod group;
 mod group_member;
 mod owner;
 mod user;
 mod webhook;
+mod webhook_channel;
+mod webhook_rule;
+mod webhook_sink;
 
+pub use delivery::{Delivery, compress_payload, decompress_payload};
 pub use group::Group;
 pub use group_member::GroupMember;
 pub use owner::Owner;
 pub use user::User;
 pub use webhook::Webhook;
+pub use webhook_channel::WebhookChannel;
+pub use webhook_rule::WebhookRule;
+pub use webhook_sink::WebhookSink;
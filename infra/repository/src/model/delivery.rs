@@ -0,0 +1,235 @@
+use std::fmt::Display;
+use std::io::{Read, Write};
+use std::str::FromStr;
+
+use anyhow::Context;
+use flate2::{Compression, read::GzDecoder, write::GzEncoder};
+use indoc::formatdoc;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, mysql::MySqlRow};
+use uuid::Uuid;
+
+use domain::{DeliveryId, DeliveryStatus, Failure, WebhookId};
+
+use crate::RepositoryImpl;
+
+const TABLE_WEBHOOK_DELIVERIES: &str = "webhook_deliveries_v2";
+
+#[must_use]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, sqlx::Decode, sqlx::Encode)]
+#[sqlx(rename_all = "snake_case")]
+enum DeliveryStatusCol {
+    Accepted,
+    Filtered,
+    Rejected,
+    Error,
+    Failed,
+}
+
+#[must_use]
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow)]
+struct DeliveryRow {
+    pub id: Uuid,
+    pub webhook_id: Uuid,
+    pub provider: String,
+    pub event: Option<String>,
+    pub remote_id: Option<String>,
+    pub status: DeliveryStatusCol,
+    pub error: Option<String>,
+    pub payload: Vec<u8>,
+}
+
+#[must_use]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Delivery {
+    pub id: DeliveryId,
+    pub webhook_id: WebhookId,
+    pub provider: String,
+    pub event: Option<String>,
+    pub remote_id: Option<String>,
+    pub status: DeliveryStatus,
+    pub error: Option<String>,
+    /// gzipで圧縮したペイロード
+    pub payload: Vec<u8>,
+}
+
+impl From<DeliveryStatusCol> for DeliveryStatus {
+    fn from(value: DeliveryStatusCol) -> Self {
+        match value {
+            DeliveryStatusCol::Accepted => Self::Accepted,
+            DeliveryStatusCol::Filtered => Self::Filtered,
+            DeliveryStatusCol::Rejected => Self::Rejected,
+            DeliveryStatusCol::Error => Self::Error,
+            DeliveryStatusCol::Failed => Self::Failed,
+        }
+    }
+}
+
+impl From<DeliveryStatus> for DeliveryStatusCol {
+    fn from(value: DeliveryStatus) -> Self {
+        match value {
+            DeliveryStatus::Accepted => Self::Accepted,
+            DeliveryStatus::Filtered => Self::Filtered,
+            DeliveryStatus::Rejected => Self::Rejected,
+            DeliveryStatus::Error => Self::Error,
+            DeliveryStatus::Failed => Self::Failed,
+        }
+    }
+}
+
+impl FromStr for DeliveryStatusCol {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        DeliveryStatus::from_str(s).map(Self::from)
+    }
+}
+
+impl Display for DeliveryStatusCol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(DeliveryStatus::from(*self).as_str())
+    }
+}
+
+impl sqlx::Type<sqlx::MySql> for DeliveryStatusCol {
+    fn type_info() -> <sqlx::MySql as sqlx::Database>::TypeInfo {
+        str::type_info()
+    }
+
+    fn compatible(ty: &<sqlx::MySql as sqlx::Database>::TypeInfo) -> bool {
+        str::compatible(ty)
+    }
+}
+
+impl From<DeliveryRow> for Delivery {
+    fn from(value: DeliveryRow) -> Self {
+        let DeliveryRow {
+            id,
+            webhook_id,
+            provider,
+            event,
+            remote_id,
+            status,
+            error,
+            payload,
+        } = value;
+        Self {
+            id: id.into(),
+            webhook_id: webhook_id.into(),
+            provider,
+            event,
+            remote_id,
+            status: status.into(),
+            error,
+            payload,
+        }
+    }
+}
+
+impl<'r> FromRow<'r, MySqlRow> for Delivery {
+    fn from_row(row: &'r MySqlRow) -> sqlx::Result<Self> {
+        DeliveryRow::from_row(row).map(Self::from)
+    }
+}
+
+pub fn compress_payload(payload: &str) -> Result<Vec<u8>, Failure> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(payload.as_bytes())
+        .context("Failed to compress payload")?;
+    let compressed = encoder.finish().context("Failed to compress payload")?;
+    Ok(compressed)
+}
+
+pub fn decompress_payload(payload: &[u8]) -> Result<String, Failure> {
+    let mut decompressed = String::new();
+    GzDecoder::new(payload)
+        .read_to_string(&mut decompressed)
+        .context("Failed to decompress payload")?;
+    Ok(decompressed)
+}
+
+#[allow(dead_code)]
+impl RepositoryImpl {
+    pub(crate) async fn create_delivery(&self, d: &Delivery) -> Result<(), Failure> {
+        let query = formatdoc! {r"
+            INSERT
+            INTO `{TABLE_WEBHOOK_DELIVERIES}`
+                (`id`, `webhook_id`, `provider`, `event`, `remote_id`, `status`, `error`, `payload`)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        "};
+        sqlx::query(&query)
+            .bind(d.id.0)
+            .bind(d.webhook_id.0)
+            .bind(&d.provider)
+            .bind(&d.event)
+            .bind(&d.remote_id)
+            .bind(DeliveryStatusCol::from(d.status))
+            .bind(&d.error)
+            .bind(&d.payload)
+            .execute(&self.0)
+            .await
+            .context("Failed to create webhook delivery to DB")?;
+        Ok(())
+    }
+
+    pub(crate) async fn update_delivery(
+        &self,
+        id: &DeliveryId,
+        status: DeliveryStatus,
+        error: Option<&str>,
+    ) -> Result<(), Failure> {
+        let query = formatdoc! {r"
+            UPDATE `{TABLE_WEBHOOK_DELIVERIES}`
+            SET `status` = ?, `error` = ?
+            WHERE `id` = ?
+        "};
+        sqlx::query(&query)
+            .bind(DeliveryStatusCol::from(status))
+            .bind(error)
+            .bind(id.0)
+            .execute(&self.0)
+            .await
+            .context("Failed to update webhook delivery in DB")?;
+        Ok(())
+    }
+
+    /// `id`はUUID v7なので新しい順に並べて返す
+    pub(crate) async fn filter_deliveries_by_wid(
+        &self,
+        webhook_id: &WebhookId,
+        limit: u64,
+    ) -> Result<Vec<Delivery>, Failure> {
+        let query = formatdoc! {r"
+            SELECT *
+            FROM `{TABLE_WEBHOOK_DELIVERIES}`
+            WHERE `webhook_id` = ?
+            ORDER BY `id` DESC
+            LIMIT ?
+        "};
+        let res = sqlx::query_as(&query)
+            .bind(webhook_id.0)
+            .bind(limit)
+            .fetch_all(&self.0)
+            .await
+            .context("Failed to read-filter webhook deliveries from DB")?;
+        Ok(res)
+    }
+
+    /// `id`が`before`より小さい記録を削除する
+    pub(crate) async fn delete_deliveries_before(
+        &self,
+        before: &DeliveryId,
+    ) -> Result<(), Failure> {
+        let query = formatdoc! {r"
+            DELETE FROM `{TABLE_WEBHOOK_DELIVERIES}`
+            WHERE `id` < ?
+        "};
+        sqlx::query(&query)
+            .bind(before.0)
+            .execute(&self.0)
+            .await
+            .context("Failed to delete webhook deliveries from DB")?;
+        Ok(())
+    }
+}
@@ -2,7 +2,7 @@ use anyhow::Context;
 use indoc::formatdoc;
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
-use sqlx::{FromRow, mysql::MySqlRow};
+use sqlx::{FromRow, MySqlConnection, mysql::MySqlRow};
 use uuid::Uuid;
 
 use domain::{Failure, GroupId, UserId};
@@ -96,7 +96,10 @@ impl RepositoryImpl {
         Ok(())
     }
 
-    pub(crate) async fn create_ignore_groups(&self, gs: &[Group]) -> Result<(), Failure> {
+    pub(crate) async fn create_ignore_groups(
+        conn: &mut MySqlConnection,
+        gs: &[Group],
+    ) -> Result<(), Failure> {
         if gs.is_empty() {
             return Ok(());
         }
@@ -110,7 +113,7 @@ impl RepositoryImpl {
             .iter()
             .fold(sqlx::query(&query), |q, g| q.bind(g.id.0).bind(&g.name));
         query
-            .execute(&self.0)
+            .execute(&mut *conn)
             .await
             .context("Failed to create groups to DB")?;
         Ok(())
@@ -2,7 +2,7 @@ use anyhow::Context;
 use indoc::formatdoc;
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
-use sqlx::{FromRow, mysql::MySqlRow};
+use sqlx::{FromRow, MySqlConnection, mysql::MySqlRow};
 use uuid::Uuid;
 
 use domain::{Failure, GroupId, UserId};
@@ -136,7 +136,7 @@ impl RepositoryImpl {
     }
 
     pub(crate) async fn create_ignore_group_members(
-        &self,
+        conn: &mut MySqlConnection,
         gms: &[GroupMember],
     ) -> Result<(), Failure> {
         if gms.is_empty() {
@@ -152,7 +152,7 @@ impl RepositoryImpl {
             q.bind(gm.group_id.0).bind(gm.user_id.0)
         });
         query
-            .execute(&self.0)
+            .execute(&mut *conn)
             .await
             .context("Failed to create group members to DB")?;
         Ok(())
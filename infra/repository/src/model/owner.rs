@@ -5,7 +5,7 @@ use anyhow::Context;
 use indoc::formatdoc;
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
-use sqlx::{FromRow, mysql::MySqlRow};
+use sqlx::{FromRow, MySqlConnection, mysql::MySqlRow};
 use uuid::Uuid;
 
 use domain::{Failure, OwnerId, OwnerKind};
@@ -149,7 +149,10 @@ impl RepositoryImpl {
         Ok(())
     }
 
-    pub(crate) async fn create_ignore_owners(&self, os: &[Owner]) -> Result<(), Failure> {
+    pub(crate) async fn create_ignore_owners(
+        conn: &mut MySqlConnection,
+        os: &[Owner],
+    ) -> Result<(), Failure> {
         if os.is_empty() {
             return Ok(());
         }
@@ -165,7 +168,7 @@ impl RepositoryImpl {
                 .bind(OwnerKindCol::from(o.kind))
         });
         query
-            .execute(&self.0)
+            .execute(&mut *conn)
             .await
             .context("Failed to create owners to DB")?;
         Ok(())
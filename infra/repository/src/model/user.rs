@@ -2,7 +2,7 @@ use anyhow::Context;
 use indoc::formatdoc;
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
-use sqlx::{FromRow, mysql::MySqlRow};
+use sqlx::{FromRow, MySqlConnection, mysql::MySqlRow};
 use uuid::Uuid;
 
 use domain::{Failure, UserId};
@@ -85,7 +85,10 @@ impl RepositoryImpl {
         Ok(())
     }
 
-    pub(crate) async fn create_ignore_users(&self, us: &[User]) -> Result<(), Failure> {
+    pub(crate) async fn create_ignore_users(
+        conn: &mut MySqlConnection,
+        us: &[User],
+    ) -> Result<(), Failure> {
         if us.is_empty() {
             return Ok(());
         }
@@ -99,7 +102,7 @@ impl RepositoryImpl {
             .iter()
             .fold(sqlx::query(&query), |q, u| q.bind(u.id.0).bind(&u.name));
         query
-            .execute(&self.0)
+            .execute(&mut *conn)
             .await
             .context("Failed to create users to DB")?;
         Ok(())
@@ -2,7 +2,7 @@ use anyhow::Context;
 use indoc::formatdoc;
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
-use sqlx::{FromRow, mysql::MySqlRow};
+use sqlx::{FromRow, MySqlConnection, mysql::MySqlRow};
 use uuid::Uuid;
 
 use domain::{ChannelId, Failure, OwnerId, WebhookId};
@@ -17,6 +17,9 @@ struct WebhookRow {
     pub id: Uuid,
     pub channel_id: Uuid,
     pub owner_id: Uuid,
+    pub secret: Option<String>,
+    pub template: Option<String>,
+    pub dm: bool,
 }
 
 #[must_use]
@@ -25,6 +28,9 @@ pub struct Webhook {
     pub id: WebhookId,
     pub channel_id: ChannelId,
     pub owner_id: OwnerId,
+    pub secret: Option<String>,
+    pub template: Option<String>,
+    pub dm: bool,
 }
 
 impl From<WebhookRow> for Webhook {
@@ -33,11 +39,17 @@ impl From<WebhookRow> for Webhook {
             id,
             channel_id,
             owner_id,
+            secret,
+            template,
+            dm,
         } = value;
         Self {
             id: id.into(),
             channel_id: channel_id.into(),
             owner_id: owner_id.into(),
+            secret,
+            template,
+            dm,
         }
     }
 }
@@ -156,16 +168,22 @@ impl RepositoryImpl {
         Ok(res)
     }
 
-    pub(crate) async fn create_webhook(&self, w: Webhook) -> Result<(), Failure> {
+    pub(crate) async fn create_webhook(
+        conn: &mut MySqlConnection,
+        w: Webhook,
+    ) -> Result<(), Failure> {
         let query = formatdoc! {r"
-            INSERT INTO `{TABLE_WEBHOOKS}` (`id`, `channel_id`, `owner_id`)
-            VALUES (?, ?, ?)
+            INSERT INTO `{TABLE_WEBHOOKS}` (`id`, `channel_id`, `owner_id`, `secret`, `template`, `dm`)
+            VALUES (?, ?, ?, ?, ?, ?)
         "};
         sqlx::query(&query)
             .bind(w.id.0)
             .bind(w.channel_id.0)
             .bind(w.owner_id.0)
-            .execute(&self.0)
+            .bind(w.secret)
+            .bind(w.template)
+            .bind(w.dm)
+            .execute(&mut *conn)
             .await
             .context("Failed to create webhook to DB")?;
         Ok(())
@@ -175,14 +193,19 @@ impl RepositoryImpl {
         if ws.is_empty() {
             return Ok(());
         }
-        let values_arg = std::iter::repeat_n("(?, ?, ?)", ws.len()).join(", ");
+        let values_arg = std::iter::repeat_n("(?, ?, ?, ?, ?, ?)", ws.len()).join(", ");
         let query = formatdoc! {r"
             INSERT IGNORE
-            INTO `{TABLE_WEBHOOKS}` (`id`, `channel_id`, `owner_id`)
+            INTO `{TABLE_WEBHOOKS}` (`id`, `channel_id`, `owner_id`, `secret`, `template`, `dm`)
             VALUES {values_arg}
         "};
         let query = ws.iter().fold(sqlx::query(&query), |q, w| {
-            q.bind(w.id.0).bind(w.channel_id.0).bind(w.owner_id.0)
+            q.bind(w.id.0)
+                .bind(w.channel_id.0)
+                .bind(w.owner_id.0)
+                .bind(&w.secret)
+                .bind(&w.template)
+                .bind(w.dm)
         });
         query
             .execute(&self.0)
@@ -191,18 +214,25 @@ impl RepositoryImpl {
         Ok(())
     }
 
-    pub(crate) async fn update_webhook(&self, id: &WebhookId, w: Webhook) -> Result<(), Failure> {
+    pub(crate) async fn update_webhook(
+        conn: &mut MySqlConnection,
+        id: &WebhookId,
+        w: Webhook,
+    ) -> Result<(), Failure> {
         let query = formatdoc! {r"
             UPDATE `{TABLE_WEBHOOKS}`
-            SET `id` = ?, `channel_id` = ?, `owner_id` = ?
+            SET `id` = ?, `channel_id` = ?, `owner_id` = ?, `secret` = ?, `template` = ?, `dm` = ?
             WHERE `id` = ?
         "};
         sqlx::query(&query)
             .bind(w.id.0)
             .bind(w.channel_id.0)
             .bind(w.owner_id.0)
+            .bind(w.secret)
+            .bind(w.template)
+            .bind(w.dm)
             .bind(id.0)
-            .execute(&self.0)
+            .execute(&mut *conn)
             .await
             .context("Failed to update webhook in DB")?;
         Ok(())
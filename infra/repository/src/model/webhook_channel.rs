@@ -0,0 +1,110 @@
+use anyhow::Context;
+use indoc::formatdoc;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, MySqlConnection, mysql::MySqlRow};
+use uuid::Uuid;
+
+use domain::{ChannelId, Failure, WebhookId};
+
+use crate::RepositoryImpl;
+
+const TABLE_WEBHOOK_CHANNELS: &str = "webhook_channels_v2";
+
+#[must_use]
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow)]
+struct WebhookChannelRow {
+    pub webhook_id: Uuid,
+    pub channel_id: Uuid,
+    pub filter: Option<String>,
+}
+
+#[must_use]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct WebhookChannel {
+    pub webhook_id: WebhookId,
+    pub channel_id: ChannelId,
+    pub filter: Option<String>,
+}
+
+impl From<WebhookChannelRow> for WebhookChannel {
+    fn from(value: WebhookChannelRow) -> Self {
+        let WebhookChannelRow {
+            webhook_id,
+            channel_id,
+            filter,
+        } = value;
+        Self {
+            webhook_id: webhook_id.into(),
+            channel_id: channel_id.into(),
+            filter,
+        }
+    }
+}
+
+impl<'r> FromRow<'r, MySqlRow> for WebhookChannel {
+    fn from_row(row: &'r MySqlRow) -> sqlx::Result<Self> {
+        WebhookChannelRow::from_row(row).map(Self::from)
+    }
+}
+
+#[allow(dead_code)]
+impl RepositoryImpl {
+    pub(crate) async fn filter_webhook_channels_by_wid(
+        &self,
+        webhook_id: &WebhookId,
+    ) -> Result<Vec<WebhookChannel>, Failure> {
+        let query = formatdoc! {r"
+            SELECT *
+            FROM `{TABLE_WEBHOOK_CHANNELS}`
+            WHERE `webhook_id` = ?
+        "};
+        let res = sqlx::query_as(&query)
+            .bind(webhook_id.0)
+            .fetch_all(&self.0)
+            .await
+            .context("Failed to read-filter webhook channels from DB")?;
+        Ok(res)
+    }
+
+    pub(crate) async fn create_webhook_channels(
+        conn: &mut MySqlConnection,
+        wcs: &[WebhookChannel],
+    ) -> Result<(), Failure> {
+        if wcs.is_empty() {
+            return Ok(());
+        }
+        let values_arg = std::iter::repeat_n("(?, ?, ?)", wcs.len()).join(", ");
+        let query = formatdoc! {r"
+            INSERT
+            INTO `{TABLE_WEBHOOK_CHANNELS}` (`webhook_id`, `channel_id`, `filter`)
+            VALUES {values_arg}
+        "};
+        let query = wcs.iter().fold(sqlx::query(&query), |q, wc| {
+            q.bind(wc.webhook_id.0)
+                .bind(wc.channel_id.0)
+                .bind(&wc.filter)
+        });
+        query
+            .execute(&mut *conn)
+            .await
+            .context("Failed to create webhook channels to DB")?;
+        Ok(())
+    }
+
+    pub(crate) async fn delete_webhook_channels_by_wid(
+        conn: &mut MySqlConnection,
+        webhook_id: &WebhookId,
+    ) -> Result<(), Failure> {
+        let query = formatdoc! {r"
+            DELETE FROM `{TABLE_WEBHOOK_CHANNELS}`
+            WHERE `webhook_id` = ?
+        "};
+        sqlx::query(&query)
+            .bind(webhook_id.0)
+            .execute(&mut *conn)
+            .await
+            .context("Failed to delete webhook channels from DB")?;
+        Ok(())
+    }
+}
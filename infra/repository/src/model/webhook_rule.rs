@@ -0,0 +1,182 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use anyhow::Context;
+use indoc::formatdoc;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, MySqlConnection, mysql::MySqlRow};
+use uuid::Uuid;
+
+use domain::{ChannelId, Failure, RuleField, WebhookId};
+
+use crate::RepositoryImpl;
+
+const TABLE_WEBHOOK_RULES: &str = "webhook_rules_v2";
+
+#[must_use]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, sqlx::Decode, sqlx::Encode)]
+#[sqlx(rename_all = "snake_case")]
+enum RuleFieldCol {
+    Repo,
+    Label,
+    Conclusion,
+    Event,
+}
+
+#[must_use]
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow)]
+struct WebhookRuleRow {
+    pub webhook_id: Uuid,
+    pub position: u32,
+    pub field: RuleFieldCol,
+    pub pattern: String,
+    pub channel_id: Uuid,
+}
+
+#[must_use]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct WebhookRule {
+    pub webhook_id: WebhookId,
+    /// 評価順
+    pub position: u32,
+    pub field: RuleField,
+    pub pattern: String,
+    pub channel_id: ChannelId,
+}
+
+impl From<RuleFieldCol> for RuleField {
+    fn from(value: RuleFieldCol) -> Self {
+        match value {
+            RuleFieldCol::Repo => Self::Repo,
+            RuleFieldCol::Label => Self::Label,
+            RuleFieldCol::Conclusion => Self::Conclusion,
+            RuleFieldCol::Event => Self::Event,
+        }
+    }
+}
+
+impl From<RuleField> for RuleFieldCol {
+    fn from(value: RuleField) -> Self {
+        match value {
+            RuleField::Repo => Self::Repo,
+            RuleField::Label => Self::Label,
+            RuleField::Conclusion => Self::Conclusion,
+            RuleField::Event => Self::Event,
+        }
+    }
+}
+
+impl FromStr for RuleFieldCol {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        RuleField::from_str(s).map(Self::from)
+    }
+}
+
+impl Display for RuleFieldCol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(RuleField::from(*self).as_str())
+    }
+}
+
+impl sqlx::Type<sqlx::MySql> for RuleFieldCol {
+    fn type_info() -> <sqlx::MySql as sqlx::Database>::TypeInfo {
+        str::type_info()
+    }
+
+    fn compatible(ty: &<sqlx::MySql as sqlx::Database>::TypeInfo) -> bool {
+        str::compatible(ty)
+    }
+}
+
+impl From<WebhookRuleRow> for WebhookRule {
+    fn from(value: WebhookRuleRow) -> Self {
+        let WebhookRuleRow {
+            webhook_id,
+            position,
+            field,
+            pattern,
+            channel_id,
+        } = value;
+        Self {
+            webhook_id: webhook_id.into(),
+            position,
+            field: field.into(),
+            pattern,
+            channel_id: channel_id.into(),
+        }
+    }
+}
+
+impl<'r> FromRow<'r, MySqlRow> for WebhookRule {
+    fn from_row(row: &'r MySqlRow) -> sqlx::Result<Self> {
+        WebhookRuleRow::from_row(row).map(Self::from)
+    }
+}
+
+#[allow(dead_code)]
+impl RepositoryImpl {
+    /// `position`の順に並べて返す
+    pub(crate) async fn filter_webhook_rules_by_wid(
+        &self,
+        webhook_id: &WebhookId,
+    ) -> Result<Vec<WebhookRule>, Failure> {
+        let query = formatdoc! {r"
+            SELECT *
+            FROM `{TABLE_WEBHOOK_RULES}`
+            WHERE `webhook_id` = ?
+            ORDER BY `position`
+        "};
+        let res = sqlx::query_as(&query)
+            .bind(webhook_id.0)
+            .fetch_all(&self.0)
+            .await
+            .context("Failed to read-filter webhook rules from DB")?;
+        Ok(res)
+    }
+
+    pub(crate) async fn create_webhook_rules(
+        conn: &mut MySqlConnection,
+        wrs: &[WebhookRule],
+    ) -> Result<(), Failure> {
+        if wrs.is_empty() {
+            return Ok(());
+        }
+        let values_arg = std::iter::repeat_n("(?, ?, ?, ?, ?)", wrs.len()).join(", ");
+        let query = formatdoc! {r"
+            INSERT
+            INTO `{TABLE_WEBHOOK_RULES}` (`webhook_id`, `position`, `field`, `pattern`, `channel_id`)
+            VALUES {values_arg}
+        "};
+        let query = wrs.iter().fold(sqlx::query(&query), |q, wr| {
+            q.bind(wr.webhook_id.0)
+                .bind(wr.position)
+                .bind(RuleFieldCol::from(wr.field))
+                .bind(&wr.pattern)
+                .bind(wr.channel_id.0)
+        });
+        query
+            .execute(&mut *conn)
+            .await
+            .context("Failed to create webhook rules to DB")?;
+        Ok(())
+    }
+
+    pub(crate) async fn delete_webhook_rules_by_wid(
+        conn: &mut MySqlConnection,
+        webhook_id: &WebhookId,
+    ) -> Result<(), Failure> {
+        let query = formatdoc! {r"
+            DELETE FROM `{TABLE_WEBHOOK_RULES}`
+            WHERE `webhook_id` = ?
+        "};
+        sqlx::query(&query)
+            .bind(webhook_id.0)
+            .execute(&mut *conn)
+            .await
+            .context("Failed to delete webhook rules from DB")?;
+        Ok(())
+    }
+}
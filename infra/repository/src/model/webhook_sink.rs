@@ -0,0 +1,193 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use anyhow::Context;
+use indoc::formatdoc;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, MySqlConnection, mysql::MySqlRow};
+use uuid::Uuid;
+
+use domain::{Failure, SinkId, SinkKind, WebhookId};
+
+use crate::RepositoryImpl;
+
+const TABLE_WEBHOOK_SINKS: &str = "webhook_sinks_v2";
+
+#[must_use]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, sqlx::Decode, sqlx::Encode)]
+#[sqlx(rename_all = "snake_case")]
+enum SinkKindCol {
+    Slack,
+    Discord,
+    Http,
+}
+
+#[must_use]
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow)]
+struct WebhookSinkRow {
+    pub id: Uuid,
+    pub webhook_id: Uuid,
+    pub position: u32,
+    pub kind: SinkKindCol,
+    pub url: String,
+}
+
+#[must_use]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct WebhookSink {
+    pub id: SinkId,
+    pub webhook_id: WebhookId,
+    pub position: u32,
+    pub kind: SinkKind,
+    pub url: String,
+}
+
+impl From<SinkKindCol> for SinkKind {
+    fn from(value: SinkKindCol) -> Self {
+        match value {
+            SinkKindCol::Slack => Self::Slack,
+            SinkKindCol::Discord => Self::Discord,
+            SinkKindCol::Http => Self::Http,
+        }
+    }
+}
+
+impl From<SinkKind> for SinkKindCol {
+    fn from(value: SinkKind) -> Self {
+        match value {
+            SinkKind::Slack => Self::Slack,
+            SinkKind::Discord => Self::Discord,
+            SinkKind::Http => Self::Http,
+        }
+    }
+}
+
+impl FromStr for SinkKindCol {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        SinkKind::from_str(s).map(Self::from)
+    }
+}
+
+impl Display for SinkKindCol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(SinkKind::from(*self).as_str())
+    }
+}
+
+impl sqlx::Type<sqlx::MySql> for SinkKindCol {
+    fn type_info() -> <sqlx::MySql as sqlx::Database>::TypeInfo {
+        str::type_info()
+    }
+
+    fn compatible(ty: &<sqlx::MySql as sqlx::Database>::TypeInfo) -> bool {
+        str::compatible(ty)
+    }
+}
+
+impl From<WebhookSinkRow> for WebhookSink {
+    fn from(value: WebhookSinkRow) -> Self {
+        let WebhookSinkRow {
+            id,
+            webhook_id,
+            position,
+            kind,
+            url,
+        } = value;
+        Self {
+            id: id.into(),
+            webhook_id: webhook_id.into(),
+            position,
+            kind: kind.into(),
+            url,
+        }
+    }
+}
+
+impl<'r> FromRow<'r, MySqlRow> for WebhookSink {
+    fn from_row(row: &'r MySqlRow) -> sqlx::Result<Self> {
+        WebhookSinkRow::from_row(row).map(Self::from)
+    }
+}
+
+#[allow(dead_code)]
+impl RepositoryImpl {
+    pub(crate) async fn find_webhook_sink(&self, id: &SinkId) -> Result<WebhookSink, Failure> {
+        let query = formatdoc! {r"
+            SELECT *
+            FROM `{TABLE_WEBHOOK_SINKS}`
+            WHERE `id` = ?
+            LIMIT 1
+        "};
+        sqlx::query_as(&query)
+            .bind(id.0)
+            .fetch_optional(&self.0)
+            .await
+            .context("Failed to read a webhook sink from DB")?
+            .ok_or_else(|| Failure::reject_not_found("No webhook sink found"))
+    }
+
+    /// `position`の順に並べて返す
+    pub(crate) async fn filter_webhook_sinks_by_wid(
+        &self,
+        webhook_id: &WebhookId,
+    ) -> Result<Vec<WebhookSink>, Failure> {
+        let query = formatdoc! {r"
+            SELECT *
+            FROM `{TABLE_WEBHOOK_SINKS}`
+            WHERE `webhook_id` = ?
+            ORDER BY `position`
+        "};
+        let res = sqlx::query_as(&query)
+            .bind(webhook_id.0)
+            .fetch_all(&self.0)
+            .await
+            .context("Failed to read-filter webhook sinks from DB")?;
+        Ok(res)
+    }
+
+    pub(crate) async fn create_webhook_sinks(
+        conn: &mut MySqlConnection,
+        wss: &[WebhookSink],
+    ) -> Result<(), Failure> {
+        if wss.is_empty() {
+            return Ok(());
+        }
+        let values_arg = std::iter::repeat_n("(?, ?, ?, ?, ?)", wss.len()).join(", ");
+        let query = formatdoc! {r"
+            INSERT
+            INTO `{TABLE_WEBHOOK_SINKS}` (`id`, `webhook_id`, `position`, `kind`, `url`)
+            VALUES {values_arg}
+        "};
+        let query = wss.iter().fold(sqlx::query(&query), |q, ws| {
+            q.bind(ws.id.0)
+                .bind(ws.webhook_id.0)
+                .bind(ws.position)
+                .bind(SinkKindCol::from(ws.kind))
+                .bind(&ws.url)
+        });
+        query
+            .execute(&mut *conn)
+            .await
+            .context("Failed to create webhook sinks to DB")?;
+        Ok(())
+    }
+
+    pub(crate) async fn delete_webhook_sinks_by_wid(
+        conn: &mut MySqlConnection,
+        webhook_id: &WebhookId,
+    ) -> Result<(), Failure> {
+        let query = formatdoc! {r"
+            DELETE FROM `{TABLE_WEBHOOK_SINKS}`
+            WHERE `webhook_id` = ?
+        "};
+        sqlx::query(&query)
+            .bind(webhook_id.0)
+            .execute(&mut *conn)
+            .await
+            .context("Failed to delete webhook sinks from DB")?;
+        Ok(())
+    }
+}
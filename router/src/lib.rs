@@ -78,7 +78,11 @@ where
     I: Infra,
     A: App<I>,
 {
-    use webhook::{get_wh, wh_clickup, wh_gitea, wh_github};
+    use webhook::{
+        get_wh, wh_alertmanager, wh_backlog, wh_clickup, wh_container_registry, wh_discord,
+        wh_generic, wh_gitea, wh_github, wh_grafana, wh_jenkins, wh_jira, wh_linear, wh_netlify,
+        wh_sentry, wh_slack, wh_stripe, wh_text, wh_uptime, wh_vercel,
+    };
 
     let (bot, webhook_handler) = app.split();
     let state = AppStateImpl::new(Arc::clone(&infra), Arc::new(webhook_handler));
@@ -105,6 +109,70 @@ where
             "/wh/{id}/clickup",
             post(wh_clickup::<AppStateImpl<I, A::WebhookHandler>>),
         )
+        .route(
+            "/wh/{id}/sentry",
+            post(wh_sentry::<AppStateImpl<I, A::WebhookHandler>>),
+        )
+        .route(
+            "/wh/{id}/alertmanager",
+            post(wh_alertmanager::<AppStateImpl<I, A::WebhookHandler>>),
+        )
+        .route(
+            "/wh/{id}/grafana",
+            post(wh_grafana::<AppStateImpl<I, A::WebhookHandler>>),
+        )
+        .route(
+            "/wh/{id}/slack",
+            post(wh_slack::<AppStateImpl<I, A::WebhookHandler>>),
+        )
+        .route(
+            "/wh/{id}/discord",
+            post(wh_discord::<AppStateImpl<I, A::WebhookHandler>>),
+        )
+        .route(
+            "/wh/{id}/generic",
+            post(wh_generic::<AppStateImpl<I, A::WebhookHandler>>),
+        )
+        .route(
+            "/wh/{id}/text",
+            post(wh_text::<AppStateImpl<I, A::WebhookHandler>>),
+        )
+        .route(
+            "/wh/{id}/jira",
+            post(wh_jira::<AppStateImpl<I, A::WebhookHandler>>),
+        )
+        .route(
+            "/wh/{id}/linear",
+            post(wh_linear::<AppStateImpl<I, A::WebhookHandler>>),
+        )
+        .route(
+            "/wh/{id}/backlog",
+            post(wh_backlog::<AppStateImpl<I, A::WebhookHandler>>),
+        )
+        .route(
+            "/wh/{id}/registry",
+            post(wh_container_registry::<AppStateImpl<I, A::WebhookHandler>>),
+        )
+        .route(
+            "/wh/{id}/uptime",
+            post(wh_uptime::<AppStateImpl<I, A::WebhookHandler>>),
+        )
+        .route(
+            "/wh/{id}/jenkins",
+            post(wh_jenkins::<AppStateImpl<I, A::WebhookHandler>>),
+        )
+        .route(
+            "/wh/{id}/vercel",
+            post(wh_vercel::<AppStateImpl<I, A::WebhookHandler>>),
+        )
+        .route(
+            "/wh/{id}/netlify",
+            post(wh_netlify::<AppStateImpl<I, A::WebhookHandler>>),
+        )
+        .route(
+            "/wh/{id}/stripe",
+            post(wh_stripe::<AppStateImpl<I, A::WebhookHandler>>),
+        )
         .with_state(state)
 }
 
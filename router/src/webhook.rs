@@ -1,12 +1,22 @@
+use std::borrow::Cow;
+
 use axum::{
     Json,
-    extract::{FromRequestParts, Path, State},
+    body::Bytes,
+    extract::{
+        FromRequest, FromRequestParts, Path, Query, Request, State, rejection::BytesRejection,
+    },
     response::IntoResponse,
 };
-use http::{HeaderMap, StatusCode, request::Parts};
+use http::{
+    HeaderMap, HeaderValue, StatusCode,
+    header::{AUTHORIZATION, CONTENT_TYPE},
+    request::Parts,
+};
+use serde::Deserialize;
 use tracing::{debug, instrument, warn};
 
-use domain::{Infra, Repository, Webhook, WebhookId};
+use domain::{Delivery, DeliveryStatus, Failure, Infra, Repository, Webhook, WebhookId};
 use usecases::{WebhookHandler, WebhookKind};
 
 use crate::{
@@ -27,8 +37,8 @@ pub enum WhRejection {
     Logic(#[from] Error),
 }
 
-impl From<domain::Failure> for WhRejection {
-    fn from(value: domain::Failure) -> Self {
+impl From<Failure> for WhRejection {
+    fn from(value: Failure) -> Self {
         Error::from(value).into()
     }
 }
@@ -55,6 +65,83 @@ where
     }
 }
 
+/// リクエストボディ。読み取れなかったリクエストも記録できるよう、受け取ったバイト列のまま持つ
+#[must_use]
+#[derive(Debug, Clone)]
+pub struct Body {
+    bytes: Bytes,
+    form: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct FormPayload {
+    payload: String,
+}
+
+impl Body {
+    /// UTF-8のテキストとして読む
+    fn text(&self) -> Result<&str, Failure> {
+        std::str::from_utf8(&self.bytes)
+            .map_err(|_| Failure::reject_bad_request("Received non UTF-8 body"))
+    }
+
+    /// `application/x-www-form-urlencoded`で送られた場合は`payload`フィールドの中身
+    fn payload(&self) -> Result<String, Failure> {
+        if !self.form {
+            return self.text().map(ToString::to_string);
+        }
+        let FormPayload { payload } = serde_urlencoded::from_bytes(&self.bytes)
+            .map_err(|e| Failure::reject_bad_request(format!("Received invalid form: {e}")))?;
+        Ok(payload)
+    }
+
+    fn lossy(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.bytes)
+    }
+}
+
+impl<S> FromRequest<S> for Body
+where
+    S: Send + Sync,
+{
+    type Rejection = BytesRejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let form = req
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("application/x-www-form-urlencoded"));
+        let bytes = Bytes::from_request(req, state).await?;
+        Ok(Body { bytes, form })
+    }
+}
+
+/// サービスごとの処理に渡す前に拒否したリクエストも`webhook deliveries`で分かるよう記録する
+async fn record_rejection<S, T>(
+    st: &S,
+    kind: WebhookKind,
+    webhook: &Webhook,
+    body: &Body,
+    res: Result<T, Failure>,
+) -> Result<T>
+where
+    S: AppState,
+{
+    let failure = match res {
+        Ok(value) => return Ok(value),
+        Err(failure) => failure,
+    };
+    warn!("{failure}");
+    let delivery = Delivery::new(webhook.id, kind.as_str(), &body.lossy())
+        .with_status(DeliveryStatus::Rejected, Some(failure.to_string()));
+    // 記録に失敗してもレスポンスは変えない
+    if let Err(e) = st.infra().repo().add_delivery(&delivery).await {
+        warn!("failed to record delivery: {e}");
+    }
+    Err(failure.into())
+}
+
 /// GET /wh/:id
 #[instrument(skip_all, fields(webhook_id = %webhook.id))]
 pub(super) async fn get_wh<S>(Wh(webhook): Wh) -> Json<Webhook>
@@ -71,12 +158,14 @@ pub(super) async fn wh_github<S>(
     State(st): State<S>,
     Wh(webhook): Wh,
     headers: HeaderMap,
-    payload: String,
+    body: Body,
 ) -> Result<StatusCode>
 where
     S: AppState,
 {
     debug!("POST github webhook");
+    let payload =
+        record_rejection(&st, WebhookKind::GitHub, &webhook, &body, body.payload()).await?;
     let infra = st.infra();
     st.webhook_handler()
         .handle(WebhookKind::GitHub, infra, webhook, headers, &payload)
@@ -91,12 +180,14 @@ pub(super) async fn wh_gitea<S>(
     State(st): State<S>,
     Wh(webhook): Wh,
     headers: HeaderMap,
-    payload: String,
+    body: Body,
 ) -> Result<StatusCode>
 where
     S: AppState,
 {
     debug!("POST gitea webhook");
+    let payload =
+        record_rejection(&st, WebhookKind::Gitea, &webhook, &body, body.payload()).await?;
     let infra = st.infra();
     st.webhook_handler()
         .handle(WebhookKind::Gitea, infra, webhook, headers, &payload)
@@ -111,15 +202,402 @@ pub(super) async fn wh_clickup<S>(
     State(st): State<S>,
     Wh(webhook): Wh,
     headers: HeaderMap,
-    payload: String,
+    body: Body,
 ) -> Result<StatusCode>
 where
     S: AppState,
 {
     debug!("POST clickup webhook");
+    let payload = record_rejection(&st, WebhookKind::Clickup, &webhook, &body, body.text()).await?;
+    let infra = st.infra();
+    st.webhook_handler()
+        .handle(WebhookKind::Clickup, infra, webhook, headers, payload)
+        .await
+        .inspect_err(|e| warn!("{e}"))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// POST /wh/:id/sentry
+#[instrument(skip_all, fields(webhook_id = %webhook.id))]
+pub(super) async fn wh_sentry<S>(
+    State(st): State<S>,
+    Wh(webhook): Wh,
+    headers: HeaderMap,
+    body: Body,
+) -> Result<StatusCode>
+where
+    S: AppState,
+{
+    debug!("POST sentry webhook");
+    let payload = record_rejection(&st, WebhookKind::Sentry, &webhook, &body, body.text()).await?;
+    let infra = st.infra();
+    st.webhook_handler()
+        .handle(WebhookKind::Sentry, infra, webhook, headers, payload)
+        .await
+        .inspect_err(|e| warn!("{e}"))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// POST /wh/:id/alertmanager
+#[instrument(skip_all, fields(webhook_id = %webhook.id))]
+pub(super) async fn wh_alertmanager<S>(
+    State(st): State<S>,
+    Wh(webhook): Wh,
+    headers: HeaderMap,
+    body: Body,
+) -> Result<StatusCode>
+where
+    S: AppState,
+{
+    debug!("POST alertmanager webhook");
+    let payload =
+        record_rejection(&st, WebhookKind::Alertmanager, &webhook, &body, body.text()).await?;
+    let infra = st.infra();
+    st.webhook_handler()
+        .handle(WebhookKind::Alertmanager, infra, webhook, headers, payload)
+        .await
+        .inspect_err(|e| warn!("{e}"))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// POST /wh/:id/grafana
+#[instrument(skip_all, fields(webhook_id = %webhook.id))]
+pub(super) async fn wh_grafana<S>(
+    State(st): State<S>,
+    Wh(webhook): Wh,
+    headers: HeaderMap,
+    body: Body,
+) -> Result<StatusCode>
+where
+    S: AppState,
+{
+    debug!("POST grafana webhook");
+    let payload = record_rejection(&st, WebhookKind::Grafana, &webhook, &body, body.text()).await?;
+    let infra = st.infra();
+    st.webhook_handler()
+        .handle(WebhookKind::Grafana, infra, webhook, headers, payload)
+        .await
+        .inspect_err(|e| warn!("{e}"))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// POST /wh/:id/slack
+///
+/// Slackのincoming webhookと同様に、成功時は`ok`を返す
+#[instrument(skip_all, fields(webhook_id = %webhook.id))]
+pub(super) async fn wh_slack<S>(
+    State(st): State<S>,
+    Wh(webhook): Wh,
+    headers: HeaderMap,
+    body: Body,
+) -> Result<&'static str>
+where
+    S: AppState,
+{
+    debug!("POST slack webhook");
+    let payload =
+        record_rejection(&st, WebhookKind::Slack, &webhook, &body, body.payload()).await?;
+    let infra = st.infra();
+    st.webhook_handler()
+        .handle(WebhookKind::Slack, infra, webhook, headers, &payload)
+        .await
+        .inspect_err(|e| warn!("{e}"))?;
+    Ok("ok")
+}
+
+/// POST /wh/:id/discord
+///
+/// `multipart/form-data`で添付ファイルが送られることがあるため、UTF-8として不正なバイト列も受け付ける
+#[instrument(skip_all, fields(webhook_id = %webhook.id))]
+pub(super) async fn wh_discord<S>(
+    State(st): State<S>,
+    Wh(webhook): Wh,
+    headers: HeaderMap,
+    payload: Bytes,
+) -> Result<StatusCode>
+where
+    S: AppState,
+{
+    debug!("POST discord webhook");
+    let infra = st.infra();
+    let payload = String::from_utf8_lossy(&payload);
+    st.webhook_handler()
+        .handle(WebhookKind::Discord, infra, webhook, headers, &payload)
+        .await
+        .inspect_err(|e| warn!("{e}"))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// POST /wh/:id/generic
+#[instrument(skip_all, fields(webhook_id = %webhook.id))]
+pub(super) async fn wh_generic<S>(
+    State(st): State<S>,
+    Wh(webhook): Wh,
+    headers: HeaderMap,
+    body: Body,
+) -> Result<StatusCode>
+where
+    S: AppState,
+{
+    debug!("POST generic webhook");
+    let payload =
+        record_rejection(&st, WebhookKind::Generic, &webhook, &body, body.payload()).await?;
+    let infra = st.infra();
+    st.webhook_handler()
+        .handle(WebhookKind::Generic, infra, webhook, headers, &payload)
+        .await
+        .inspect_err(|e| warn!("{e}"))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub(super) struct TokenQuery {
+    token: Option<String>,
+}
+
+/// POST /wh/:id/text
+///
+/// `?token=...`で渡されたトークンは`Authorization: Bearer ...`として扱う
+#[instrument(skip_all, fields(webhook_id = %webhook.id))]
+pub(super) async fn wh_text<S>(
+    State(st): State<S>,
+    Wh(webhook): Wh,
+    Query(query): Query<TokenQuery>,
+    mut headers: HeaderMap,
+    body: Body,
+) -> Result<StatusCode>
+where
+    S: AppState,
+{
+    debug!("POST text webhook");
+    let payload = record_rejection(&st, WebhookKind::Text, &webhook, &body, body.text()).await?;
+    if let Some(token) = query.token
+        && !headers.contains_key(AUTHORIZATION)
+    {
+        let value = HeaderValue::try_from(format!("Bearer {token}"))
+            .map_err(|_| Failure::reject_bad_request("Received invalid token"));
+        let value = record_rejection(&st, WebhookKind::Text, &webhook, &body, value).await?;
+        headers.insert(AUTHORIZATION, value);
+    }
+    let infra = st.infra();
+    st.webhook_handler()
+        .handle(WebhookKind::Text, infra, webhook, headers, payload)
+        .await
+        .inspect_err(|e| warn!("{e}"))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// POST /wh/:id/jira
+#[instrument(skip_all, fields(webhook_id = %webhook.id))]
+pub(super) async fn wh_jira<S>(
+    State(st): State<S>,
+    Wh(webhook): Wh,
+    headers: HeaderMap,
+    body: Body,
+) -> Result<StatusCode>
+where
+    S: AppState,
+{
+    debug!("POST jira webhook");
+    let payload = record_rejection(&st, WebhookKind::Jira, &webhook, &body, body.text()).await?;
+    let infra = st.infra();
+    st.webhook_handler()
+        .handle(WebhookKind::Jira, infra, webhook, headers, payload)
+        .await
+        .inspect_err(|e| warn!("{e}"))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// POST /wh/:id/linear
+#[instrument(skip_all, fields(webhook_id = %webhook.id))]
+pub(super) async fn wh_linear<S>(
+    State(st): State<S>,
+    Wh(webhook): Wh,
+    headers: HeaderMap,
+    body: Body,
+) -> Result<StatusCode>
+where
+    S: AppState,
+{
+    debug!("POST linear webhook");
+    let payload = record_rejection(&st, WebhookKind::Linear, &webhook, &body, body.text()).await?;
+    let infra = st.infra();
+    st.webhook_handler()
+        .handle(WebhookKind::Linear, infra, webhook, headers, payload)
+        .await
+        .inspect_err(|e| warn!("{e}"))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub(super) struct BacklogQuery {
+    space: Option<String>,
+}
+
+/// POST /wh/:id/backlog
+///
+/// `?space=example.backlog.com`で渡されたスペースは`X-Backlog-Space`ヘッダーとして扱う
+#[instrument(skip_all, fields(webhook_id = %webhook.id))]
+pub(super) async fn wh_backlog<S>(
+    State(st): State<S>,
+    Wh(webhook): Wh,
+    Query(query): Query<BacklogQuery>,
+    mut headers: HeaderMap,
+    body: Body,
+) -> Result<StatusCode>
+where
+    S: AppState,
+{
+    debug!("POST backlog webhook");
+    let payload = record_rejection(&st, WebhookKind::Backlog, &webhook, &body, body.text()).await?;
+    if let Some(space) = query.space {
+        let value = HeaderValue::try_from(space)
+            .map_err(|_| Failure::reject_bad_request("Received invalid space"));
+        let value = record_rejection(&st, WebhookKind::Backlog, &webhook, &body, value).await?;
+        headers.insert("X-Backlog-Space", value);
+    }
+    let infra = st.infra();
+    st.webhook_handler()
+        .handle(WebhookKind::Backlog, infra, webhook, headers, payload)
+        .await
+        .inspect_err(|e| warn!("{e}"))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// POST /wh/:id/registry
+#[instrument(skip_all, fields(webhook_id = %webhook.id))]
+pub(super) async fn wh_container_registry<S>(
+    State(st): State<S>,
+    Wh(webhook): Wh,
+    headers: HeaderMap,
+    body: Body,
+) -> Result<StatusCode>
+where
+    S: AppState,
+{
+    debug!("POST container registry webhook");
+    let payload = record_rejection(
+        &st,
+        WebhookKind::ContainerRegistry,
+        &webhook,
+        &body,
+        body.text(),
+    )
+    .await?;
+    let infra = st.infra();
+    st.webhook_handler()
+        .handle(
+            WebhookKind::ContainerRegistry,
+            infra,
+            webhook,
+            headers,
+            payload,
+        )
+        .await
+        .inspect_err(|e| warn!("{e}"))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// POST /wh/:id/uptime
+#[instrument(skip_all, fields(webhook_id = %webhook.id))]
+pub(super) async fn wh_uptime<S>(
+    State(st): State<S>,
+    Wh(webhook): Wh,
+    headers: HeaderMap,
+    body: Body,
+) -> Result<StatusCode>
+where
+    S: AppState,
+{
+    debug!("POST uptime webhook");
+    let payload = record_rejection(&st, WebhookKind::Uptime, &webhook, &body, body.text()).await?;
+    let infra = st.infra();
+    st.webhook_handler()
+        .handle(WebhookKind::Uptime, infra, webhook, headers, payload)
+        .await
+        .inspect_err(|e| warn!("{e}"))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// POST /wh/:id/jenkins
+#[instrument(skip_all, fields(webhook_id = %webhook.id))]
+pub(super) async fn wh_jenkins<S>(
+    State(st): State<S>,
+    Wh(webhook): Wh,
+    headers: HeaderMap,
+    body: Body,
+) -> Result<StatusCode>
+where
+    S: AppState,
+{
+    debug!("POST jenkins webhook");
+    let payload = record_rejection(&st, WebhookKind::Jenkins, &webhook, &body, body.text()).await?;
+    let infra = st.infra();
+    st.webhook_handler()
+        .handle(WebhookKind::Jenkins, infra, webhook, headers, payload)
+        .await
+        .inspect_err(|e| warn!("{e}"))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// POST /wh/:id/vercel
+#[instrument(skip_all, fields(webhook_id = %webhook.id))]
+pub(super) async fn wh_vercel<S>(
+    State(st): State<S>,
+    Wh(webhook): Wh,
+    headers: HeaderMap,
+    body: Body,
+) -> Result<StatusCode>
+where
+    S: AppState,
+{
+    debug!("POST vercel webhook");
+    let payload = record_rejection(&st, WebhookKind::Vercel, &webhook, &body, body.text()).await?;
+    let infra = st.infra();
+    st.webhook_handler()
+        .handle(WebhookKind::Vercel, infra, webhook, headers, payload)
+        .await
+        .inspect_err(|e| warn!("{e}"))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// POST /wh/:id/netlify
+#[instrument(skip_all, fields(webhook_id = %webhook.id))]
+pub(super) async fn wh_netlify<S>(
+    State(st): State<S>,
+    Wh(webhook): Wh,
+    headers: HeaderMap,
+    body: Body,
+) -> Result<StatusCode>
+where
+    S: AppState,
+{
+    debug!("POST netlify webhook");
+    let payload = record_rejection(&st, WebhookKind::Netlify, &webhook, &body, body.text()).await?;
+    let infra = st.infra();
+    st.webhook_handler()
+        .handle(WebhookKind::Netlify, infra, webhook, headers, payload)
+        .await
+        .inspect_err(|e| warn!("{e}"))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// POST /wh/:id/stripe
+#[instrument(skip_all, fields(webhook_id = %webhook.id))]
+pub(super) async fn wh_stripe<S>(
+    State(st): State<S>,
+    Wh(webhook): Wh,
+    headers: HeaderMap,
+    body: Body,
+) -> Result<StatusCode>
+where
+    S: AppState,
+{
+    debug!("POST stripe webhook");
+    let payload = record_rejection(&st, WebhookKind::Stripe, &webhook, &body, body.text()).await?;
     let infra = st.infra();
     st.webhook_handler()
-        .handle(WebhookKind::Clickup, infra, webhook, headers, &payload)
+        .handle(WebhookKind::Stripe, infra, webhook, headers, payload)
         .await
         .inspect_err(|e| warn!("{e}"))?;
     Ok(StatusCode::NO_CONTENT)
@@ -0,0 +1,75 @@
+//! メールしか送れない機器からの通知を受け取るためのSMTPサーバー
+//!
+//! `<webhook id>@<domain>`宛てのメールを、そのWebhookの投稿先チャンネルに流す
+
+mod session;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::net::TcpListener;
+
+use domain::Infra;
+use usecases::WebhookHandler;
+
+/// 1行の最大長。RFC 5321の制限 (1000オクテット) に余裕を持たせている
+const MAX_LINE_LENGTH: usize = 4096;
+
+/// コマンドを待つ時間。RFC 5321の推奨値は5分
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// 1通のメールで指定できる宛先の最大数
+const MAX_RECIPIENTS: usize = 16;
+
+#[must_use]
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// 受け付ける宛先のドメイン
+    pub domain: String,
+    /// 受け付けるメールの最大サイズ (バイト)
+    pub max_message_size: usize,
+}
+
+#[must_use]
+pub struct Server<I, WH>
+where
+    I: Infra,
+    WH: WebhookHandler<I>,
+{
+    config: Arc<Config>,
+    infra: Arc<I>,
+    webhook_handler: Arc<WH>,
+}
+
+impl<I, WH> Server<I, WH>
+where
+    I: Infra,
+    WH: WebhookHandler<I>,
+{
+    pub fn new(config: Config, infra: Arc<I>, webhook_handler: Arc<WH>) -> Self {
+        Self {
+            config: Arc::new(config),
+            infra,
+            webhook_handler,
+        }
+    }
+
+    /// never returns unless `accept` fails
+    #[tracing::instrument(skip_all, fields(domain = self.config.domain))]
+    pub async fn serve(self, listener: TcpListener) -> std::io::Result<()> {
+        loop {
+            let (stream, addr) = listener.accept().await?;
+            tracing::debug!(%addr, "accepted SMTP connection");
+            let session = session::Session::new(
+                Arc::clone(&self.config),
+                Arc::clone(&self.infra),
+                Arc::clone(&self.webhook_handler),
+            );
+            tokio::spawn(async move {
+                if let Err(e) = session.run(stream).await {
+                    tracing::warn!(%addr, error = ?e, "SMTP session failed");
+                }
+            });
+        }
+    }
+}
@@ -0,0 +1,295 @@
+use std::io;
+use std::sync::Arc;
+
+use http::HeaderMap;
+use tokio::io::{
+    AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt,
+};
+use tokio::io::{BufReader, split};
+use tokio::time::timeout;
+
+use domain::{Failure, Infra, Repository, Webhook, WebhookId};
+use usecases::{WebhookHandler, WebhookKind};
+
+use crate::{COMMAND_TIMEOUT, Config, MAX_LINE_LENGTH, MAX_RECIPIENTS};
+
+/// 1つの接続に対応するSMTPのやり取り
+///
+/// 認証やSTARTTLSには対応せず、`<webhook id>@<domain>`以外の宛先は拒否する
+pub(crate) struct Session<I, WH>
+where
+    I: Infra,
+    WH: WebhookHandler<I>,
+{
+    config: Arc<Config>,
+    infra: Arc<I>,
+    webhook_handler: Arc<WH>,
+    greeted: bool,
+    reverse_path: Option<String>,
+    recipients: Vec<Webhook>,
+}
+
+/// 1行読んだ結果
+enum Line {
+    Eof,
+    TooLong,
+    Complete(String),
+}
+
+impl<I, WH> Session<I, WH>
+where
+    I: Infra,
+    WH: WebhookHandler<I>,
+{
+    pub(crate) fn new(config: Arc<Config>, infra: Arc<I>, webhook_handler: Arc<WH>) -> Self {
+        Self {
+            config,
+            infra,
+            webhook_handler,
+            greeted: false,
+            reverse_path: None,
+            recipients: vec![],
+        }
+    }
+
+    pub(crate) async fn run<S>(mut self, stream: S) -> io::Result<()>
+    where
+        S: AsyncRead + AsyncWrite + Send + Unpin,
+    {
+        let (reader, mut writer) = split(stream);
+        let mut reader = BufReader::new(reader);
+        let greeting = format!("220 {} ESMTP ready", self.config.domain);
+        reply(&mut writer, &greeting).await?;
+        loop {
+            let Ok(line) = timeout(COMMAND_TIMEOUT, read_line(&mut reader)).await else {
+                reply(&mut writer, "421 4.4.2 Timeout").await?;
+                return Ok(());
+            };
+            let line = match line? {
+                Line::Eof => return Ok(()),
+                Line::TooLong => {
+                    reply(&mut writer, "500 5.5.2 Line too long").await?;
+                    return Ok(());
+                }
+                Line::Complete(line) => line,
+            };
+            let (verb, args) = line.split_once(' ').unwrap_or((&line, ""));
+            let response = match verb.to_ascii_uppercase().as_str() {
+                "HELO" => self.helo(false),
+                "EHLO" => self.helo(true),
+                "MAIL" => self.mail(args),
+                "RCPT" => self.rcpt(args).await,
+                "DATA" => self.data(&mut reader, &mut writer).await?,
+                "RSET" => {
+                    self.reset();
+                    "250 2.0.0 OK".to_string()
+                }
+                "NOOP" => "250 2.0.0 OK".to_string(),
+                "VRFY" => "252 2.1.5 Cannot verify user".to_string(),
+                "QUIT" => {
+                    reply(&mut writer, "221 2.0.0 Bye").await?;
+                    return Ok(());
+                }
+                _ => "502 5.5.1 Command not implemented".to_string(),
+            };
+            reply(&mut writer, &response).await?;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.reverse_path = None;
+        self.recipients.clear();
+    }
+
+    fn helo(&mut self, extended: bool) -> String {
+        self.reset();
+        self.greeted = true;
+        let domain = &self.config.domain;
+        if extended {
+            let size = self.config.max_message_size;
+            format!("250-{domain}\r\n250-SIZE {size}\r\n250-8BITMIME\r\n250 PIPELINING")
+        } else {
+            format!("250 {domain}")
+        }
+    }
+
+    /// `MAIL FROM:<reverse-path> [SIZE=<size>]`
+    fn mail(&mut self, args: &str) -> String {
+        if !self.greeted {
+            return "503 5.5.1 Send HELO/EHLO first".to_string();
+        }
+        if self.reverse_path.is_some() {
+            return "503 5.5.1 Nested MAIL command".to_string();
+        }
+        let Some(path) = strip_prefix_ignore_case(args.trim(), "FROM:") else {
+            return "501 5.5.4 Syntax: MAIL FROM:<address>".to_string();
+        };
+        let size = path
+            .split_whitespace()
+            .skip(1)
+            .find_map(|p| strip_prefix_ignore_case(p, "SIZE="))
+            .and_then(|s| s.parse::<usize>().ok());
+        if size.is_some_and(|s| s > self.config.max_message_size) {
+            return "552 5.3.4 Message size exceeds fixed limit".to_string();
+        }
+        let address = path.split_whitespace().next().unwrap_or_default();
+        self.reverse_path = Some(address.trim_matches(['<', '>']).to_string());
+        "250 2.1.0 OK".to_string()
+    }
+
+    /// `RCPT TO:<webhook id>@<domain>`
+    async fn rcpt(&mut self, args: &str) -> String {
+        if self.reverse_path.is_none() {
+            return "503 5.5.1 Send MAIL first".to_string();
+        }
+        let Some(path) = strip_prefix_ignore_case(args.trim(), "TO:") else {
+            return "501 5.5.4 Syntax: RCPT TO:<address>".to_string();
+        };
+        if self.recipients.len() >= MAX_RECIPIENTS {
+            return "452 4.5.3 Too many recipients".to_string();
+        }
+        let address = path.split_whitespace().next().unwrap_or_default();
+        let address = address.trim_matches(['<', '>']);
+        let webhook_id = address
+            .rsplit_once('@')
+            .filter(|(_, domain)| domain.eq_ignore_ascii_case(&self.config.domain))
+            .and_then(|(local, _)| local.parse::<uuid::Uuid>().ok())
+            .map(WebhookId::from);
+        let Some(webhook_id) = webhook_id else {
+            return "550 5.1.1 Unknown recipient".to_string();
+        };
+        match self.infra.repo().find_webhook(&webhook_id).await {
+            Ok(webhook) => {
+                self.recipients.push(webhook);
+                "250 2.1.5 OK".to_string()
+            }
+            Err(Failure::Reject(_)) => "550 5.1.1 Unknown recipient".to_string(),
+            Err(Failure::Error(e)) => {
+                tracing::error!(error = ?e, "failed to find webhook");
+                "451 4.3.0 Local error in processing".to_string()
+            }
+        }
+    }
+
+    async fn data<R, W>(&mut self, reader: &mut R, writer: &mut W) -> io::Result<String>
+    where
+        R: AsyncBufRead + Send + Unpin,
+        W: AsyncWrite + Send + Unpin,
+    {
+        if self.recipients.is_empty() {
+            return Ok("503 5.5.1 Send RCPT first".to_string());
+        }
+        reply(writer, "354 End data with <CR><LF>.<CR><LF>").await?;
+        let max_size = self.config.max_message_size;
+        let Ok(data) = timeout(COMMAND_TIMEOUT, read_data(reader, max_size)).await else {
+            // コマンドの待ち受けと同様に、切断する前に知らせる
+            reply(writer, "421 4.4.2 Timeout").await?;
+            return Err(io::ErrorKind::TimedOut.into());
+        };
+        let recipients = std::mem::take(&mut self.recipients);
+        self.reset();
+        let Some(data) = data? else {
+            return Ok("552 5.3.4 Message size exceeds fixed limit".to_string());
+        };
+        let message = String::from_utf8_lossy(&data);
+        let mut response = "250 2.0.0 OK".to_string();
+        for webhook in recipients {
+            let res = self
+                .webhook_handler
+                .handle(
+                    WebhookKind::Email,
+                    &*self.infra,
+                    webhook,
+                    HeaderMap::new(),
+                    &message,
+                )
+                .await;
+            match res {
+                Ok(()) => {}
+                Err(Failure::Reject(r)) => {
+                    tracing::warn!("{r}");
+                    response = format!("554 5.6.0 {}", r.as_message());
+                }
+                Err(Failure::Error(e)) => {
+                    tracing::error!(error = ?e, "failed to handle email");
+                    response = "451 4.3.0 Local error in processing".to_string();
+                }
+            }
+        }
+        Ok(response)
+    }
+}
+
+async fn reply<W>(writer: &mut W, response: &str) -> io::Result<()>
+where
+    W: AsyncWrite + Unpin,
+{
+    writer.write_all(response.as_bytes()).await?;
+    writer.write_all(b"\r\n").await?;
+    writer.flush().await
+}
+
+async fn read_line<R>(reader: &mut R) -> io::Result<Line>
+where
+    R: AsyncBufRead + Unpin,
+{
+    let mut buf = vec![];
+    let limit = MAX_LINE_LENGTH as u64;
+    let read = reader.take(limit).read_until(b'\n', &mut buf).await?;
+    if read == 0 {
+        return Ok(Line::Eof);
+    }
+    if !buf.ends_with(b"\n") {
+        return Ok(Line::TooLong);
+    }
+    let line = String::from_utf8_lossy(&buf);
+    Ok(Line::Complete(
+        line.trim_end_matches(['\r', '\n']).to_string(),
+    ))
+}
+
+/// `.`だけの行までを読み、先頭の`.`のエスケープを戻す
+///
+/// `max_size`を超えた場合も終端までは読み進めて`None`を返す
+async fn read_data<R>(reader: &mut R, max_size: usize) -> io::Result<Option<Vec<u8>>>
+where
+    R: AsyncBufRead + Unpin,
+{
+    let mut data = vec![];
+    let mut too_large = false;
+    let mut at_line_start = true;
+    let mut buf = vec![];
+    let limit = MAX_LINE_LENGTH as u64;
+    loop {
+        buf.clear();
+        let read = (&mut *reader)
+            .take(limit)
+            .read_until(b'\n', &mut buf)
+            .await?;
+        if read == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        if at_line_start && matches!(buf.as_slice(), b".\r\n" | b".\n") {
+            break;
+        }
+        let content = match buf.strip_prefix(b".") {
+            Some(content) if at_line_start => content,
+            _ => &buf,
+        };
+        at_line_start = buf.ends_with(b"\n");
+        if data.len() + content.len() > max_size {
+            too_large = true;
+            data = vec![];
+        }
+        if !too_large {
+            data.extend_from_slice(content);
+        }
+    }
+    Ok((!too_large).then_some(data))
+}
+
+fn strip_prefix_ignore_case<'a>(s: &'a str, prefix: &str) -> Option<&'a str> {
+    let head = s.get(..prefix.len())?;
+    head.eq_ignore_ascii_case(prefix)
+        .then(|| s[prefix.len()..].trim_start())
+}
@@ -1,6 +1,7 @@
 pub mod signal;
 pub mod wrappers;
 
+use std::net::SocketAddr;
 use std::time::Duration;
 
 use serde::{Deserialize, Serialize};
@@ -38,6 +39,64 @@ impl TryFrom<CronConfig> for Duration {
     }
 }
 
+/// 受け取ったWebhookの記録の設定
+#[must_use]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct DeliveryConfig {
+    /// 記録を残す日数
+    pub delivery_retention_days: u64,
+}
+
+impl Default for DeliveryConfig {
+    fn default() -> Self {
+        Self {
+            delivery_retention_days: 14,
+        }
+    }
+}
+
+impl DeliveryConfig {
+    pub fn retention(&self) -> Duration {
+        Duration::from_secs(self.delivery_retention_days * 24 * 60 * 60)
+    }
+}
+
+/// メールを受け取るSMTPサーバーの設定
+///
+/// `SMTP_DOMAIN`が設定されていない場合はSMTPサーバーを起動しない
+#[must_use]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SmtpConfig {
+    pub smtp_domain: Option<String>,
+    pub smtp_port: u16,
+    pub smtp_max_message_size: usize,
+}
+
+impl Default for SmtpConfig {
+    fn default() -> Self {
+        Self {
+            smtp_domain: None,
+            smtp_port: 2525,
+            smtp_max_message_size: 1024 * 1024,
+        }
+    }
+}
+
+impl SmtpConfig {
+    #[must_use]
+    pub fn into_server_config(self) -> Option<(smtp::Config, SocketAddr)> {
+        let domain = self.smtp_domain.filter(|d| !d.is_empty())?;
+        let config = smtp::Config {
+            domain,
+            max_message_size: self.smtp_max_message_size,
+        };
+        let addr = SocketAddr::from(([0, 0, 0, 0], self.smtp_port));
+        Some((config, addr))
+    }
+}
+
 #[must_use]
 #[derive(Debug, Clone)]
 pub struct ConfigComposite {
@@ -46,6 +105,8 @@ pub struct ConfigComposite {
     pub client_config: wrappers::infra::TraqClientConfig,
     pub repo_config: wrappers::infra::RepoConfig,
     pub cron_config: CronConfig,
+    pub smtp_config: SmtpConfig,
+    pub delivery_config: DeliveryConfig,
 }
 
 impl ConfigComposite {
@@ -56,6 +117,8 @@ impl ConfigComposite {
             client_config: envy::from_env()?,
             repo_config: wrappers::infra::RepoConfig::from_env()?,
             cron_config: envy::from_env().unwrap_or_default(),
+            smtp_config: envy::from_env()?,
+            delivery_config: envy::from_env()?,
         })
     }
 }
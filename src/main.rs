@@ -1,11 +1,15 @@
 use bot_cnvtr as lib;
 
 use std::sync::Arc;
+use std::time::Duration;
 
 use tracing_subscriber::EnvFilter;
 
 use lib::{ConfigComposite, wrappers};
 
+/// 古い配信記録を削除する間隔
+const PRUNE_PERIOD: Duration = Duration::from_secs(60 * 60);
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let env_filter = EnvFilter::try_from_env("CNVTR_LOG")
@@ -19,6 +23,8 @@ async fn main() -> anyhow::Result<()> {
         client_config,
         repo_config,
         cron_config,
+        smtp_config,
+        delivery_config,
     } = ConfigComposite::from_env()
         .map_err(anyhow::Error::from)
         .or_else(|_| -> anyhow::Result<_> {
@@ -31,7 +37,8 @@ async fn main() -> anyhow::Result<()> {
     let repo = repo_opt.connect().await?;
     repo.migrate().await?;
     let (tx, rx) = cron::channel();
-    let infra = wrappers::InfraImpl::new_wrapped(repo, client, tx);
+    let sink = event_sink::EventSinkImpl::new();
+    let infra = wrappers::InfraImpl::new_wrapped(repo, client, tx, sink);
     let infra = Arc::new(infra);
 
     // run notifier in background
@@ -43,6 +50,27 @@ async fn main() -> anyhow::Result<()> {
         })
     };
 
+    // prune old delivery logs in background
+    let pruner_handle = {
+        let infra = Arc::clone(&infra);
+        let pruner = cron::Pruner::new(delivery_config.retention());
+        tokio::task::spawn(async move {
+            pruner.run(infra, PRUNE_PERIOD).await;
+        })
+    };
+
+    // run SMTP server in background if configured
+    let smtp_handle = match smtp_config.into_server_config() {
+        Some((config, addr)) => {
+            let listener = tokio::net::TcpListener::bind(addr).await?;
+            tracing::info!("SMTP listening on {addr} ...");
+            let wh = Arc::new(wh_handler::WebhookHandlerImpl::new());
+            let server = smtp::Server::new(config, Arc::clone(&infra), wh);
+            Some(tokio::task::spawn(server.serve(listener)))
+        }
+        None => None,
+    };
+
     let bot = bot::BotImpl::builder()
         .verification_token(&bot_config.verification_token)
         .name(&bot_config.name)
@@ -65,6 +93,10 @@ async fn main() -> anyhow::Result<()> {
             res?;
         }
         _ = cron_handle => unreachable!(),
+        _ = pruner_handle => unreachable!(),
+        Some(res) = async { Some(smtp_handle?.await) } => {
+            res??;
+        }
     }
     Ok(())
 }
@@ -1,40 +1,44 @@
 pub mod app;
 pub mod infra;
 
-use domain::{EventSubscriber, Infra, Repository, TraqClient};
+use domain::{EventSink, EventSubscriber, Infra, Repository, TraqClient};
 
 // use app::{BotWrapper, WHandlerWrapper};
-use infra::{EventSubWrapper, RepoWrapper, TraqClientWrapper};
+use infra::{EventSinkWrapper, EventSubWrapper, RepoWrapper, TraqClientWrapper};
 
 #[must_use]
-pub struct InfraImpl<R, C, S>(pub R, pub C, pub S);
+pub struct InfraImpl<R, C, S, K>(pub R, pub C, pub S, pub K);
 
-impl<R: Repository, C: TraqClient, S: EventSubscriber> InfraImpl<R, C, S> {
-    pub fn new(repo: R, client: C, subscriber: S) -> Self {
-        Self(repo, client, subscriber)
+impl<R: Repository, C: TraqClient, S: EventSubscriber, K: EventSink> InfraImpl<R, C, S, K> {
+    pub fn new(repo: R, client: C, subscriber: S, sink: K) -> Self {
+        Self(repo, client, subscriber, sink)
     }
 }
 
-impl<R: Repository, C: TraqClient, S: EventSubscriber>
-    InfraImpl<RepoWrapper<R>, TraqClientWrapper<C>, EventSubWrapper<S>>
+impl<R: Repository, C: TraqClient, S: EventSubscriber, K: EventSink>
+    InfraImpl<RepoWrapper<R>, TraqClientWrapper<C>, EventSubWrapper<S>, EventSinkWrapper<K>>
 {
-    pub fn new_wrapped(repo: R, client: C, subscriber: S) -> Self {
+    pub fn new_wrapped(repo: R, client: C, subscriber: S, sink: K) -> Self {
         let repo = RepoWrapper(repo);
         let client = TraqClientWrapper(client);
         let subscriber = EventSubWrapper(subscriber);
-        Self(repo, client, subscriber)
+        let sink = EventSinkWrapper(sink);
+        Self(repo, client, subscriber, sink)
     }
 }
 
-impl<R, C, S> Infra for InfraImpl<RepoWrapper<R>, TraqClientWrapper<C>, EventSubWrapper<S>>
+impl<R, C, S, K> Infra
+    for InfraImpl<RepoWrapper<R>, TraqClientWrapper<C>, EventSubWrapper<S>, EventSinkWrapper<K>>
 where
     R: Repository,
     C: TraqClient,
     S: EventSubscriber + Clone,
+    K: EventSink,
 {
     type Repo = RepoWrapper<R>;
     type TClient = TraqClientWrapper<C>;
     type ESub = EventSubWrapper<S>;
+    type ESink = EventSinkWrapper<K>;
 
     fn repo(&self) -> &Self::Repo {
         &self.0
@@ -47,4 +51,8 @@ where
     fn event_subscriber(&self) -> &Self::ESub {
         &self.2
     }
+
+    fn event_sink(&self) -> &Self::ESink {
+        &self.3
+    }
 }
@@ -1,8 +1,10 @@
 use serde::{Deserialize, Serialize};
 
+use std::time::SystemTime;
+
 use domain::{
-    ChannelId, Event, EventSubscriber, Failure, GroupId, MessageId, Repository, StampId,
-    TraqClient, UserId, WebhookId,
+    ChannelId, Delivery, DeliveryId, DeliveryStatus, Event, EventSink, EventSubscriber, Failure,
+    GroupId, MessageId, Repository, Sink, SinkId, StampId, TraqClient, UserId, WebhookId,
 };
 use repository::opt;
 
@@ -16,6 +18,15 @@ impl<S: EventSubscriber + Clone> EventSubscriber for EventSubWrapper<S> {
     }
 }
 
+#[must_use]
+pub struct EventSinkWrapper<K: EventSink>(pub K);
+
+impl<K: EventSink> EventSink for EventSinkWrapper<K> {
+    async fn send(&self, sink: &Sink, event: &Event) -> Result<(), Failure> {
+        self.0.send(sink, event).await
+    }
+}
+
 #[must_use]
 pub struct RepoWrapper<R: Repository>(pub R);
 
@@ -28,6 +39,10 @@ impl<R: Repository> Repository for RepoWrapper<R> {
         self.0.remove_webhook(webhook).await
     }
 
+    async fn update_webhook(&self, webhook: &domain::Webhook) -> Result<(), Failure> {
+        self.0.update_webhook(webhook).await
+    }
+
     async fn list_webhooks(&self) -> Result<Vec<domain::Webhook>, Failure> {
         self.0.list_webhooks().await
     }
@@ -36,6 +51,10 @@ impl<R: Repository> Repository for RepoWrapper<R> {
         self.0.find_webhook(id).await
     }
 
+    async fn find_sink(&self, id: &SinkId) -> Result<Sink, Failure> {
+        self.0.find_sink(id).await
+    }
+
     async fn filter_webhook_by_owner(
         &self,
         owner: &domain::Owner,
@@ -56,6 +75,31 @@ impl<R: Repository> Repository for RepoWrapper<R> {
     ) -> Result<Vec<domain::Webhook>, Failure> {
         self.0.filter_webhook_by_user(user).await
     }
+
+    async fn add_delivery(&self, delivery: &Delivery) -> Result<(), Failure> {
+        self.0.add_delivery(delivery).await
+    }
+
+    async fn update_delivery_status(
+        &self,
+        id: &DeliveryId,
+        status: DeliveryStatus,
+        error: Option<&str>,
+    ) -> Result<(), Failure> {
+        self.0.update_delivery_status(id, status, error).await
+    }
+
+    async fn filter_deliveries_by_webhook(
+        &self,
+        webhook_id: &WebhookId,
+        limit: usize,
+    ) -> Result<Vec<Delivery>, Failure> {
+        self.0.filter_deliveries_by_webhook(webhook_id, limit).await
+    }
+
+    async fn remove_deliveries_before(&self, before: SystemTime) -> Result<(), Failure> {
+        self.0.remove_deliveries_before(before).await
+    }
 }
 
 #[must_use]
@@ -8,6 +8,52 @@ pub enum WebhookKind {
     GitHub,
     Gitea,
     Clickup,
+    Sentry,
+    Alertmanager,
+    Grafana,
+    Slack,
+    Discord,
+    Generic,
+    Text,
+    Jira,
+    Linear,
+    Backlog,
+    ContainerRegistry,
+    Uptime,
+    Jenkins,
+    Vercel,
+    Netlify,
+    Stripe,
+    Email,
+}
+
+impl WebhookKind {
+    /// 配信記録などに使う名前
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::GitHub => "github",
+            Self::Gitea => "gitea",
+            Self::Clickup => "clickup",
+            Self::Sentry => "sentry",
+            Self::Alertmanager => "alertmanager",
+            Self::Grafana => "grafana",
+            Self::Slack => "slack",
+            Self::Discord => "discord",
+            Self::Generic => "generic",
+            Self::Text => "text",
+            Self::Jira => "jira",
+            Self::Linear => "linear",
+            Self::Backlog => "backlog",
+            Self::ContainerRegistry => "container-registry",
+            Self::Uptime => "uptime",
+            Self::Jenkins => "jenkins",
+            Self::Vercel => "vercel",
+            Self::Netlify => "netlify",
+            Self::Stripe => "stripe",
+            Self::Email => "email",
+        }
+    }
 }
 
 #[must_use]